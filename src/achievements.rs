@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+
+use crate::language::Text;
+use crate::stats::{LifetimeStats, RunStats};
+use crate::storage::write_atomically;
+
+const ACHIEVEMENTS_FILE: &str = "achievements.json";
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Achievement {
+    /// Destroy 100 enemies over all runs
+    Destroy100Enemies,
+    /// Reach a 10x combo in one run
+    Combo10,
+    /// Survive five minutes in one run
+    Survive5Minutes,
+    /// Beat a high score that was already set
+    NewHighScore,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [
+        Achievement::Destroy100Enemies,
+        Achievement::Combo10,
+        Achievement::Survive5Minutes,
+        Achievement::NewHighScore,
+    ];
+
+    pub fn label(self) -> Text {
+        match self {
+            Achievement::Destroy100Enemies => Text::Destroy100Enemies,
+            Achievement::Combo10 => Text::Combo10,
+            Achievement::Survive5Minutes => Text::Survive5Minutes,
+            Achievement::NewHighScore => Text::NewHighScore,
+        }
+    }
+
+    /// Whether the run in progress has earned the achievement.
+    fn is_earned(
+        self,
+        run: &RunStats,
+        lifetime: &LifetimeStats,
+        high_score: u32,
+        score: u32,
+    ) -> bool {
+        match self {
+            Achievement::Destroy100Enemies => {
+                lifetime.enemies_destroyed + run.enemies_destroyed >= 100
+            }
+            Achievement::Combo10 => run.max_combo >= 10,
+            Achievement::Survive5Minutes => run.time_survived >= 300.0,
+            Achievement::NewHighScore => high_score > 0 && score > high_score,
+        }
+    }
+}
+
+/// Keeps track of which achievements have been unlocked, saved in
+/// `achievements.json`.
+pub struct AchievementTracker {
+    unlocked: Vec<Achievement>,
+}
+
+impl AchievementTracker {
+    /// Reads the unlocked achievements, starting with none if the file is
+    /// missing. A file that can't be parsed is moved aside to a backup
+    /// instead of being overwritten.
+    pub fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(ACHIEVEMENTS_FILE) else {
+            return Self {
+                unlocked: Vec::new(),
+            };
+        };
+        let unlocked = serde_json::from_str(&contents).unwrap_or_else(|err| {
+            let backup_file = format!("{}.bak", ACHIEVEMENTS_FILE);
+            eprintln!(
+                "Warning: couldn't read {} ({}), moving it to {}",
+                ACHIEVEMENTS_FILE, err, backup_file
+            );
+            fs::rename(ACHIEVEMENTS_FILE, backup_file).ok();
+            Vec::new()
+        });
+        Self { unlocked }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let contents = serde_json::to_string_pretty(&self.unlocked)?;
+        write_atomically(ACHIEVEMENTS_FILE, &contents)
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+
+    /// Unlocks the achievements the run in progress has earned, where
+    /// `high_score` is the high score from before the run, returning the ones
+    /// that are new.
+    pub fn check(
+        &mut self,
+        run: &RunStats,
+        lifetime: &LifetimeStats,
+        high_score: u32,
+        score: u32,
+    ) -> Vec<Achievement> {
+        let earned: Vec<Achievement> = Achievement::ALL
+            .into_iter()
+            .filter(|&achievement| {
+                !self.is_unlocked(achievement)
+                    && achievement.is_earned(run, lifetime, high_score, score)
+            })
+            .collect();
+        if !earned.is_empty() {
+            self.unlocked.extend(&earned);
+            self.save().ok();
+        }
+        earned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn destroy_100_earned(lifetime_destroyed: u32, run_destroyed: u32) -> bool {
+        let run = RunStats {
+            enemies_destroyed: run_destroyed,
+            ..Default::default()
+        };
+        let lifetime = LifetimeStats {
+            enemies_destroyed: lifetime_destroyed,
+            ..Default::default()
+        };
+        Achievement::Destroy100Enemies.is_earned(&run, &lifetime, 0, 0)
+    }
+
+    #[test]
+    fn destroy_100_enemies_is_earned_at_exactly_100() {
+        assert!(!destroy_100_earned(0, 99));
+        assert!(destroy_100_earned(0, 100));
+        assert!(!destroy_100_earned(60, 39));
+        assert!(destroy_100_earned(60, 40));
+        assert!(!destroy_100_earned(99, 0));
+        assert!(destroy_100_earned(100, 0));
+    }
+}
@@ -0,0 +1,114 @@
+use macroquad::audio::{
+    load_sound, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound,
+};
+use macroquad::rand;
+
+/// Owns the game's sounds and plays them at the configured volumes.
+pub struct AudioManager {
+    music: Option<Sound>,
+    explosion: Option<Sound>,
+    laser: Option<Sound>,
+    hit: Option<Sound>,
+    heartbeat: Option<Sound>,
+    extra_life: Option<Sound>,
+    enabled: bool,
+    music_volume: f32,
+    sfx_volume: f32,
+    music_started: bool,
+}
+
+impl AudioManager {
+    /// Loads all sounds. The game still runs without sound if any of the
+    /// audio files are missing.
+    pub async fn load(enabled: bool) -> Self {
+        Self {
+            music: load_sound("assets/8bit-spaceshooter.ogg").await.ok(),
+            explosion: load_sound("assets/explosion.wav").await.ok(),
+            laser: load_sound("assets/laser.wav").await.ok(),
+            hit: load_sound("assets/hit.wav").await.ok(),
+            heartbeat: load_sound("assets/heartbeat.wav").await.ok(),
+            extra_life: load_sound("assets/extra_life.wav").await.ok(),
+            enabled,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            music_started: false,
+        }
+    }
+
+    /// Starts the looping theme music the first time it is called.
+    pub fn start_music(&mut self) {
+        if self.music_started {
+            return;
+        }
+        self.music_started = true;
+        if self.enabled {
+            self.play_music();
+        }
+    }
+
+    /// Mutes or unmutes all audio, resuming the music if it had been started.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if let Some(music) = &self.music {
+            if !enabled {
+                stop_sound(music);
+            } else if self.music_started {
+                self.play_music();
+            }
+        }
+    }
+
+    pub fn set_volumes(&mut self, music_volume: f32, sfx_volume: f32) {
+        self.music_volume = music_volume.clamp(0.0, 1.0);
+        self.sfx_volume = sfx_volume.clamp(0.0, 1.0);
+        if let Some(music) = &self.music {
+            set_sound_volume(music, self.music_volume);
+        }
+    }
+
+    pub fn play_shoot(&self) {
+        self.play_effect(&self.laser);
+    }
+
+    pub fn play_explosion(&self) {
+        self.play_effect(&self.explosion);
+    }
+
+    pub fn play_hit(&self) {
+        self.play_effect(&self.hit);
+    }
+
+    pub fn play_heartbeat(&self) {
+        self.play_effect(&self.heartbeat);
+    }
+
+    pub fn play_extra_life(&self) {
+        self.play_effect(&self.extra_life);
+    }
+
+    fn play_music(&self) {
+        if let Some(music) = &self.music {
+            play_sound(
+                music,
+                PlaySoundParams {
+                    looped: true,
+                    volume: self.music_volume,
+                },
+            );
+        }
+    }
+
+    /// macroquad can't change the pitch of a sound, so the volume is varied a
+    /// little instead to keep repeated effects from sounding identical.
+    fn play_effect(&self, sound: &Option<Sound>) {
+        if let (Some(sound), true) = (sound, self.enabled) {
+            play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: false,
+                    volume: self.sfx_volume * rand::gen_range(0.75, 1.0),
+                },
+            );
+        }
+    }
+}
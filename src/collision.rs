@@ -0,0 +1,130 @@
+use macroquad::prelude::*;
+
+use std::collections::HashMap;
+
+/// Buckets entities by the screen cells they overlap, so that collision
+/// checks only have to look at entities that are close to each other.
+pub struct CollisionGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl CollisionGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Empties the grid while keeping the cells allocated for the next frame.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    /// Adds the entity with the given index to every cell its rectangle overlaps.
+    pub fn insert(&mut self, index: usize, rect: Rect) {
+        for cell in self.cells_overlapping(rect) {
+            self.cells.entry(cell).or_default().push(index);
+        }
+    }
+
+    /// Returns the indices of the entities sharing a cell with the rectangle,
+    /// each only once and in ascending order.
+    pub fn query(&self, rect: Rect) -> Vec<usize> {
+        let mut indices: Vec<usize> = self
+            .cells_overlapping(rect)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    fn cells_overlapping(&self, rect: Rect) -> impl Iterator<Item = (i32, i32)> {
+        let min_x = (rect.x / self.cell_size).floor() as i32;
+        let min_y = (rect.y / self.cell_size).floor() as i32;
+        let max_x = ((rect.x + rect.w) / self.cell_size).floor() as i32;
+        let max_y = ((rect.y + rect.h) / self.cell_size).floor() as i32;
+        (min_x..=max_x).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    /// A small linear congruential generator, so the test sees the same
+    /// rectangles on every run.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        fn rect(&mut self) -> Rect {
+            // Often wider than a cell, so plenty of them span cell borders
+            Rect::new(
+                self.next() * 1600.0 - 100.0,
+                self.next() * 1200.0 - 100.0,
+                self.next() * 80.0,
+                self.next() * 80.0,
+            )
+        }
+    }
+
+    #[test]
+    fn grid_finds_the_same_overlapping_pairs_as_brute_force() {
+        let mut rng = Lcg(42);
+        let mut grid = CollisionGrid::new(50.0);
+        for _ in 0..5 {
+            grid.clear();
+            let rects: Vec<Rect> = (0..3000).map(|_| rng.rect()).collect();
+            for (index, rect) in rects.iter().enumerate() {
+                grid.insert(index, *rect);
+            }
+
+            let mut expected = HashSet::new();
+            for (i, a) in rects.iter().enumerate() {
+                for (j, b) in rects.iter().enumerate().skip(i + 1) {
+                    if a.overlaps(b) {
+                        expected.insert((i, j));
+                    }
+                }
+            }
+            let mut found = HashSet::new();
+            for (i, a) in rects.iter().enumerate() {
+                let candidates = grid.query(*a);
+                assert!(candidates.windows(2).all(|pair| pair[0] < pair[1]));
+                for j in candidates {
+                    if j > i && a.overlaps(&rects[j]) {
+                        found.insert((i, j));
+                    }
+                }
+            }
+            assert!(!expected.is_empty());
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn query_finds_a_rect_spanning_cells_from_each_cell() {
+        let mut grid = CollisionGrid::new(50.0);
+        grid.insert(0, Rect::new(40.0, 40.0, 70.0, 70.0));
+        for (x, y) in [(45.0, 45.0), (60.0, 45.0), (45.0, 60.0), (105.0, 105.0)] {
+            assert_eq!(grid.query(Rect::new(x, y, 1.0, 1.0)), vec![0]);
+        }
+        assert!(grid.query(Rect::new(200.0, 200.0, 1.0, 1.0)).is_empty());
+    }
+}
@@ -0,0 +1,25 @@
+#version 100
+
+// Faint scanlines and darkened corners drawn over the finished frame, to
+// make it look like it is shown on an old CRT screen.
+
+precision lowp float;
+
+varying vec2 uv;
+
+uniform sampler2D Texture;
+uniform vec2 iResolution;
+
+void main()
+{
+    vec3 col = texture2D(Texture, uv).rgb;
+
+    // Every other row of pixels is a little darker
+    float scanline = mod(floor(uv.y * iResolution.y), 2.);
+    col *= 1. - scanline * .2;
+
+    float vignette = smoothstep(.3, .8, length(uv - .5));
+    col *= 1. - vignette * .6;
+
+    gl_FragColor = vec4(col, 1.0);
+}
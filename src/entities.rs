@@ -0,0 +1,1025 @@
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+
+use std::collections::VecDeque;
+
+use crate::language::Text;
+use crate::palette::Palette;
+
+const MOVEMENT_SPEED: f32 = 200.0;
+/// How quickly the ship speeds up with inertia, in pixels per second squared
+const INERTIA_ACCELERATION: f32 = 800.0;
+/// Share of its velocity the ship loses per second with inertia
+const INERTIA_FRICTION: f32 = 2.0;
+/// Top speed with inertia, compared to the speed without it
+const INERTIA_MAX_SPEED_SCALE: f32 = 1.5;
+const STARTING_LIVES: u32 = 3;
+/// Most lives the player can have, beyond which extra lives are forfeited
+pub const MAX_LIVES: u32 = 5;
+const STARTING_BOMBS: u32 = 2;
+/// Enemy size that makes up one hit point.
+const HP_PER_SIZE: f32 = 24.0;
+const ZIGZAG_SPEED: f32 = 150.0;
+const ZIGZAG_FREQUENCY: f32 = 3.0;
+/// Seconds into a run before homing enemies start to appear
+const HOMING_UNLOCK_TIME: f32 = 30.0;
+const HOMING_MAX_SPEED: f32 = 180.0;
+/// How quickly a homing enemy can change its velocity, in pixels per second squared
+const HOMING_TURN_RATE: f32 = 120.0;
+const ENEMY_FIRE_INTERVAL: f32 = 2.5;
+const ENEMY_BULLET_SPEED: f32 = 220.0;
+/// Seconds a new enemy takes to grow to its full size on screen
+const SPAWN_GROW_TIME: f32 = 0.2;
+/// Number of columns the top of the screen is split into when picking where
+/// an enemy spawns
+const SPAWN_COLUMNS: usize = 8;
+/// How many of the latest spawn columns are avoided by the next spawn
+const SPAWN_HISTORY: usize = 4;
+/// How many times over a splitter breaks into smaller pieces
+const SPLIT_GENERATIONS: u32 = 2;
+/// Size of each piece of a splitter compared to the one it broke off from
+const SPLIT_SIZE_SCALE: f32 = 0.6;
+/// Speed of each piece of a splitter compared to the one it broke off from
+const SPLIT_SPEED_SCALE: f32 = 1.5;
+/// Angle between the directions of neighbouring pieces, in radians
+const SPLIT_SPREAD: f32 = 0.7;
+const RESPAWN_INVINCIBILITY: f64 = 2.0;
+/// How far a hit in practice pushes the ship
+const KNOCK_BACK_DISTANCE: f32 = 80.0;
+/// Seconds the ship can't be hit again after being pushed away in practice
+const KNOCK_BACK_INVINCIBILITY: f64 = 1.0;
+/// How far outside the hitbox a near miss counts as a graze
+const GRAZE_MARGIN: f32 = 16.0;
+/// Seconds between shots while the fire key is held
+const FIRE_COOLDOWN: f64 = 0.25;
+/// Seconds the fire button has to be held before a shot starts charging
+const CHARGE_DELAY: f32 = 0.3;
+/// Seconds of charging that give the strongest charged shot
+const FULL_CHARGE_TIME: f32 = 1.2;
+const RAPID_FIRE_DURATION: f64 = 8.0;
+const SPREAD_DURATION: f64 = 10.0;
+const MAGNET_DURATION: f64 = 10.0;
+const TIME_SLOW_DURATION: f64 = 6.0;
+const POWER_UP_SPEED: f32 = 80.0;
+/// Seconds a score popup stays on screen
+const FLOATING_TEXT_LIFETIME: f32 = 0.8;
+/// How fast a score popup rises, in pixels per second
+const FLOATING_TEXT_RISE: f32 = 40.0;
+const BOSS_HP: u32 = 40;
+const BOSS_SIZE: f32 = 96.0;
+/// Height at which the boss stops descending and starts sweeping sideways
+const BOSS_SWEEP_HEIGHT: f32 = 120.0;
+const BOSS_ENTRY_SPEED: f32 = 60.0;
+const BOSS_SWEEP_SPEED: f32 = 120.0;
+
+pub trait Collider {
+    fn rect(&self) -> Rect;
+
+    fn collides_with(&self, other: &impl Collider) -> bool {
+        self.rect().overlaps(&other.rect())
+    }
+}
+
+pub struct Shape {
+    pub size: f32,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub collided: bool,
+    /// Whether passing close to the player has already earned a graze bonus
+    pub grazed: bool,
+    /// Hit points a bullet knocks off what it hits
+    pub damage: u32,
+    /// How many more enemies a bullet can go through before it is used up
+    pub pierce_count: u32,
+    /// Ids of the enemies a piercing bullet has gone through, so it only
+    /// hits each of them once
+    pub pierced: Vec<u32>,
+}
+
+impl Shape {
+    pub fn update(&mut self, dt: f32) {
+        self.x += self.vx * dt;
+        self.y += self.vy * dt;
+    }
+}
+
+impl Collider for Shape {
+    fn rect(&self) -> Rect {
+        Rect {
+            x: self.x - self.size / 2.0,
+            y: self.y - self.size / 2.0,
+            w: self.size,
+            h: self.size,
+        }
+    }
+}
+
+/// The player's bullets, kept in slots that are reused instead of freed so
+/// firing doesn't allocate once the pool fits the busiest moment of a run.
+pub struct BulletPool {
+    slots: Vec<PooledBullet>,
+}
+
+struct PooledBullet {
+    bullet: Shape,
+    active: bool,
+}
+
+impl BulletPool {
+    pub fn new() -> Self {
+        Self { slots: vec![] }
+    }
+
+    /// Places a bullet in the first free slot, only growing the pool if all
+    /// slots are in use.
+    pub fn spawn(&mut self, bullet: Shape) {
+        match self.slots.iter_mut().find(|slot| !slot.active) {
+            Some(slot) => {
+                slot.bullet = bullet;
+                slot.active = true;
+            }
+            None => self.slots.push(PooledBullet {
+                bullet,
+                active: true,
+            }),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Shape> {
+        self.slots
+            .iter()
+            .filter(|slot| slot.active)
+            .map(|slot| &slot.bullet)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Shape> {
+        self.slots
+            .iter_mut()
+            .filter(|slot| slot.active)
+            .map(|slot| &mut slot.bullet)
+    }
+
+    /// Frees the slots of the bullets for which `keep` returns false.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Shape) -> bool) {
+        for slot in self.slots.iter_mut().filter(|slot| slot.active) {
+            slot.active = keep(&slot.bullet);
+        }
+    }
+
+    /// Frees every slot while keeping them allocated for the next run.
+    pub fn clear(&mut self) {
+        for slot in &mut self.slots {
+            slot.active = false;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EnemyKind {
+    Grunt,
+    Fast,
+    Tank,
+    /// Mustn't be shot, costing score instead of earning it
+    Friendly,
+    /// Breaks into smaller, faster pieces when destroyed
+    Splitter,
+}
+
+impl EnemyKind {
+    pub fn random(rng: &RandGenerator) -> Self {
+        match rng.gen_range(0, 100) {
+            0..=49 => EnemyKind::Grunt,
+            50..=74 => EnemyKind::Fast,
+            75..=87 => EnemyKind::Tank,
+            88..=92 => EnemyKind::Splitter,
+            _ => EnemyKind::Friendly,
+        }
+    }
+
+    pub fn color(self, palette: &Palette) -> Color {
+        match self {
+            EnemyKind::Grunt => palette.grunt,
+            EnemyKind::Fast => palette.fast,
+            EnemyKind::Tank => palette.tank,
+            EnemyKind::Friendly => palette.friendly,
+            EnemyKind::Splitter => palette.splitter,
+        }
+    }
+
+    pub fn extra_hp(self) -> u32 {
+        match self {
+            EnemyKind::Grunt | EnemyKind::Fast | EnemyKind::Friendly | EnemyKind::Splitter => 0,
+            EnemyKind::Tank => 2,
+        }
+    }
+
+    /// Whether enemies of this kind fire back at the player.
+    pub fn is_armed(self) -> bool {
+        self == EnemyKind::Tank
+    }
+
+    /// Score awarded per unit of enemy size.
+    pub fn score_multiplier(self) -> u32 {
+        match self {
+            EnemyKind::Grunt | EnemyKind::Friendly => 1,
+            EnemyKind::Fast | EnemyKind::Splitter => 2,
+            EnemyKind::Tank => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Movement {
+    Straight,
+    /// Weaves from side to side while descending
+    ZigZag,
+    /// Steers toward the player until it has passed them
+    Homing,
+}
+
+impl Movement {
+    /// Picks a movement pattern, only allowing homing enemies once the run
+    /// has lasted for a while.
+    pub fn random(elapsed_time: f32, rng: &RandGenerator) -> Self {
+        if elapsed_time >= HOMING_UNLOCK_TIME && rng.gen_range(0, 10) == 0 {
+            Movement::Homing
+        } else if rng.gen_range(0, 4) == 0 {
+            Movement::ZigZag
+        } else {
+            Movement::Straight
+        }
+    }
+
+    pub fn score_multiplier(self) -> u32 {
+        match self {
+            Movement::Straight | Movement::ZigZag => 1,
+            Movement::Homing => 2,
+        }
+    }
+}
+
+pub struct Enemy {
+    /// Tells enemies apart for as long as a run lasts
+    pub id: u32,
+    pub kind: EnemyKind,
+    pub movement: Movement,
+    pub size: f32,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub hp: u32,
+    pub collided: bool,
+    /// Whether passing close to the player has already earned a graze bonus
+    pub grazed: bool,
+    /// Seconds since the enemy spawned
+    pub age: f32,
+    /// Horizontal direction of the zig-zag, flipped when bouncing off an edge
+    pub sway: f32,
+    /// Seconds until the enemy fires its next bullet, if it is armed
+    pub shoot_timer: f32,
+    /// How many times the pieces of a splitter have broken apart to get to
+    /// this one, 0 for everything that spawned whole
+    pub generation: u32,
+}
+
+impl Enemy {
+    /// Creates a random enemy of the given kind just above the top of the
+    /// screen, with its speed scaled by `speed_scale`. `column` is how far
+    /// across the screen it spawns, from 0.0 at the left to 1.0 at the right.
+    pub fn spawn(
+        id: u32,
+        kind: EnemyKind,
+        movement: Movement,
+        speed_scale: f32,
+        column: f32,
+        rng: &RandGenerator,
+    ) -> Self {
+        let (size, speed) = match kind {
+            EnemyKind::Grunt => (rng.gen_range(16.0, 64.0), rng.gen_range(50.0, 150.0)),
+            EnemyKind::Fast => (rng.gen_range(16.0, 32.0), rng.gen_range(150.0, 250.0)),
+            EnemyKind::Friendly => (rng.gen_range(24.0, 48.0), rng.gen_range(50.0, 120.0)),
+            EnemyKind::Tank => (rng.gen_range(48.0, 64.0), rng.gen_range(40.0, 80.0)),
+            EnemyKind::Splitter => (rng.gen_range(56.0, 64.0), rng.gen_range(40.0, 90.0)),
+        };
+        // Bigger enemies take more hits to bring down
+        let hp = (size / HP_PER_SIZE).ceil() as u32 + kind.extra_hp();
+        Self {
+            id,
+            kind,
+            movement,
+            size,
+            x: size / 2.0 + column * (screen_width() - size),
+            y: -size,
+            vx: 0.0,
+            vy: speed * speed_scale,
+            hp,
+            collided: false,
+            grazed: false,
+            age: 0.0,
+            sway: if rng.gen_range(0, 2) == 0 { 1.0 } else { -1.0 },
+            shoot_timer: rng.gen_range(0.5, ENEMY_FIRE_INTERVAL),
+            generation: 0,
+        }
+    }
+
+    /// The enemies left behind when this one is destroyed: two or three
+    /// smaller, faster pieces flying apart from where a splitter was, unless
+    /// it is already the smallest piece. Ids are handed out from `next_id`.
+    pub fn on_destroy(&self, next_id: &mut u32, rng: &RandGenerator) -> Vec<Enemy> {
+        if self.kind != EnemyKind::Splitter || self.generation >= SPLIT_GENERATIONS {
+            return Vec::new();
+        }
+        let count = rng.gen_range(2, 4);
+        let size = self.size * SPLIT_SIZE_SCALE;
+        let speed = vec2(self.vx, self.vy).length() * SPLIT_SPEED_SCALE;
+        (0..count)
+            .map(|i| {
+                // Fan out around straight down
+                let angle = (i as f32 - (count - 1) as f32 / 2.0) * SPLIT_SPREAD;
+                let velocity = Vec2::from_angle(angle).rotate(vec2(0.0, speed));
+                *next_id += 1;
+                Enemy {
+                    id: *next_id,
+                    kind: self.kind,
+                    movement: Movement::Straight,
+                    size,
+                    x: self.x,
+                    y: self.y,
+                    vx: velocity.x,
+                    vy: velocity.y,
+                    hp: (size / HP_PER_SIZE).ceil() as u32,
+                    collided: false,
+                    grazed: false,
+                    // Break off at full size rather than growing in
+                    age: SPAWN_GROW_TIME,
+                    sway: 1.0,
+                    shoot_timer: ENEMY_FIRE_INTERVAL,
+                    generation: self.generation + 1,
+                }
+            })
+            .collect()
+    }
+
+    pub fn update(&mut self, dt: f32, target: Vec2) {
+        self.age += dt;
+        match self.movement {
+            Movement::Straight => {}
+            Movement::ZigZag => {
+                self.vx = self.sway * ZIGZAG_SPEED * (self.age * ZIGZAG_FREQUENCY).sin();
+            }
+            Movement::Homing => {
+                // Stop steering once below the target so a missed pass leaves the screen
+                if self.y < target.y {
+                    let velocity = vec2(self.vx, self.vy);
+                    let desired =
+                        (target - vec2(self.x, self.y)).normalize_or_zero() * HOMING_MAX_SPEED;
+                    let steering = (desired - velocity).clamp_length_max(HOMING_TURN_RATE * dt);
+                    let velocity = (velocity + steering).clamp_length_max(HOMING_MAX_SPEED);
+                    self.vx = velocity.x;
+                    self.vy = velocity.y;
+                }
+            }
+        }
+
+        self.x += self.vx * dt;
+        self.y += self.vy * dt;
+
+        if self.movement == Movement::ZigZag {
+            // Bounce off the screen edges instead of leaving sideways
+            let half_size = self.size / 2.0;
+            if self.x < half_size || self.x > screen_width() - half_size {
+                self.x = self.x.min(screen_width() - half_size).max(half_size);
+                self.sway = -self.sway;
+            }
+        }
+    }
+
+    /// Counts down the shoot timer and returns a bullet aimed at the target
+    /// when an armed enemy is ready to fire.
+    pub fn shoot(&mut self, dt: f32, target: Vec2) -> Option<Shape> {
+        if !self.kind.is_armed() || self.y < 0.0 || self.y > target.y {
+            return None;
+        }
+        self.shoot_timer -= dt;
+        if self.shoot_timer > 0.0 {
+            return None;
+        }
+        self.shoot_timer = ENEMY_FIRE_INTERVAL;
+        let velocity = (target - vec2(self.x, self.y)).normalize_or_zero() * ENEMY_BULLET_SPEED;
+        Some(Shape {
+            size: 10.0,
+            x: self.x,
+            y: self.y + self.size / 2.0,
+            vx: velocity.x,
+            vy: velocity.y,
+            collided: false,
+            grazed: false,
+            damage: 1,
+            pierce_count: 0,
+            pierced: Vec::new(),
+        })
+    }
+
+    pub fn color(&self, palette: &Palette) -> Color {
+        // Friendlies always stand out, however they move
+        if self.kind == EnemyKind::Friendly {
+            return self.kind.color(palette);
+        }
+        match self.movement {
+            Movement::ZigZag => palette.zig_zag,
+            Movement::Homing => palette.homing,
+            Movement::Straight => self.kind.color(palette),
+        }
+    }
+
+    pub fn score(&self) -> u32 {
+        self.size.round() as u32 * self.kind.score_multiplier() * self.movement.score_multiplier()
+    }
+
+    /// How large the enemy is drawn, growing from 0.0 to 1.0 just after it
+    /// spawns. The hitbox is always full size.
+    pub fn draw_scale(&self) -> f32 {
+        (self.age / SPAWN_GROW_TIME).min(1.0)
+    }
+}
+
+/// Remembers the columns the latest enemies spawned in, so the next ones
+/// favor the quiet parts of the screen instead of piling up into walls.
+#[derive(PartialEq)]
+pub struct SpawnColumns {
+    /// Newest first
+    recent: VecDeque<usize>,
+}
+
+impl SpawnColumns {
+    pub fn new() -> Self {
+        Self {
+            recent: VecDeque::with_capacity(SPAWN_HISTORY),
+        }
+    }
+
+    /// Picks how far across the screen the next enemy spawns, from 0.0 to
+    /// 1.0. Each column is less likely the more recently an enemy spawned
+    /// in it.
+    pub fn pick(&mut self, rng: &RandGenerator) -> f32 {
+        let mut weights = [SPAWN_HISTORY + 1; SPAWN_COLUMNS];
+        for (age, &column) in self.recent.iter().enumerate() {
+            weights[column] = weights[column].saturating_sub(SPAWN_HISTORY - age);
+        }
+        // With more columns than remembered spawns, some always have weight
+        let mut roll = rng.gen_range(0, weights.iter().sum::<usize>());
+        let mut column = 0;
+        while roll >= weights[column] {
+            roll -= weights[column];
+            column += 1;
+        }
+        self.recent.push_front(column);
+        self.recent.truncate(SPAWN_HISTORY);
+        (column as f32 + rng.gen_range(0.0, 1.0)) / SPAWN_COLUMNS as f32
+    }
+}
+
+impl Collider for Enemy {
+    fn rect(&self) -> Rect {
+        Rect {
+            x: self.x - self.size / 2.0,
+            y: self.y - self.size / 2.0,
+            w: self.size,
+            h: self.size,
+        }
+    }
+}
+
+pub struct Boss {
+    pub size: f32,
+    pub x: f32,
+    pub y: f32,
+    pub hp: u32,
+    /// Horizontal direction of the sweep, flipped when reaching an edge
+    pub sweep: f32,
+}
+
+impl Boss {
+    /// Creates a boss centered just above the top of the screen.
+    pub fn spawn() -> Self {
+        Self {
+            size: BOSS_SIZE,
+            x: screen_width() / 2.0,
+            y: -BOSS_SIZE,
+            hp: BOSS_HP,
+            sweep: 1.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        // Descend into view before starting to sweep from side to side
+        if self.y < BOSS_SWEEP_HEIGHT {
+            self.y = (self.y + BOSS_ENTRY_SPEED * dt).min(BOSS_SWEEP_HEIGHT);
+            return;
+        }
+        self.x += self.sweep * BOSS_SWEEP_SPEED * dt;
+        let half_size = self.size / 2.0;
+        if self.x < half_size || self.x > screen_width() - half_size {
+            self.x = self.x.min(screen_width() - half_size).max(half_size);
+            self.sweep = -self.sweep;
+        }
+    }
+
+    /// The area the boss descends into after spawning.
+    pub fn spawn_zone() -> Rect {
+        Rect::new(
+            screen_width() / 2.0 - BOSS_SIZE / 2.0,
+            0.0,
+            BOSS_SIZE,
+            BOSS_SWEEP_HEIGHT + BOSS_SIZE / 2.0,
+        )
+    }
+
+    /// Fraction of hit points the boss has left.
+    pub fn health(&self) -> f32 {
+        self.hp as f32 / BOSS_HP as f32
+    }
+}
+
+impl Collider for Boss {
+    fn rect(&self) -> Rect {
+        Rect {
+            x: self.x - self.size / 2.0,
+            y: self.y - self.size / 2.0,
+            w: self.size,
+            h: self.size,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PowerUpKind {
+    RapidFire,
+    Spread,
+    /// Pulls coins toward the player
+    Magnet,
+    /// Slows down the enemies and their bullets, but not the ship
+    TimeSlow,
+}
+
+impl PowerUpKind {
+    pub fn random(rng: &RandGenerator) -> Self {
+        match rng.gen_range(0, 4) {
+            0 => PowerUpKind::RapidFire,
+            1 => PowerUpKind::Spread,
+            2 => PowerUpKind::Magnet,
+            _ => PowerUpKind::TimeSlow,
+        }
+    }
+
+    pub fn color(self, palette: &Palette) -> Color {
+        match self {
+            PowerUpKind::RapidFire => palette.rapid_fire,
+            PowerUpKind::Spread => palette.spread,
+            PowerUpKind::Magnet => palette.magnet,
+            PowerUpKind::TimeSlow => palette.time_slow,
+        }
+    }
+
+    pub fn name(self) -> Text {
+        match self {
+            PowerUpKind::RapidFire => Text::RapidFire,
+            PowerUpKind::Spread => Text::Spread,
+            PowerUpKind::Magnet => Text::Magnet,
+            PowerUpKind::TimeSlow => Text::TimeSlow,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerUpKind::RapidFire => "R",
+            PowerUpKind::Spread => "S",
+            PowerUpKind::Magnet => "M",
+            PowerUpKind::TimeSlow => "T",
+        }
+    }
+
+    pub fn duration(self) -> f64 {
+        match self {
+            PowerUpKind::RapidFire => RAPID_FIRE_DURATION,
+            PowerUpKind::Spread => SPREAD_DURATION,
+            PowerUpKind::Magnet => MAGNET_DURATION,
+            PowerUpKind::TimeSlow => TIME_SLOW_DURATION,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Weapon {
+    Single,
+    /// Three bullets fanned out around the firing direction
+    Spread,
+}
+
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub size: f32,
+    pub x: f32,
+    pub y: f32,
+    pub collided: bool,
+}
+
+impl PowerUp {
+    pub fn new(kind: PowerUpKind, x: f32, y: f32) -> Self {
+        Self {
+            kind,
+            size: 24.0,
+            x,
+            y,
+            collided: false,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.y += POWER_UP_SPEED * dt;
+    }
+}
+
+impl Collider for PowerUp {
+    fn rect(&self) -> Rect {
+        Rect {
+            x: self.x - self.size / 2.0,
+            y: self.y - self.size / 2.0,
+            w: self.size,
+            h: self.size,
+        }
+    }
+}
+
+/// The score gained or lost from a kill, rising and fading out where the
+/// enemy was.
+pub struct FloatingText {
+    pub x: f32,
+    pub y: f32,
+    pub value: i64,
+    /// Seconds since it appeared
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+impl FloatingText {
+    pub fn new(x: f32, y: f32, value: i64) -> Self {
+        Self {
+            x,
+            y,
+            value,
+            age: 0.0,
+            lifetime: FLOATING_TEXT_LIFETIME,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.age += dt;
+        self.y -= FLOATING_TEXT_RISE * dt;
+    }
+
+    /// How visible it still is, from 1.0 when new down to 0.0 once it has
+    /// expired.
+    pub fn alpha(&self) -> f32 {
+        (1.0 - self.age / self.lifetime).max(0.0)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.age >= self.lifetime
+    }
+}
+
+pub struct Player {
+    pub size: f32,
+    pub speed: f32,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub lives: u32,
+    pub bombs: u32,
+    /// Seconds of play since the run started, which stands still while paused
+    pub clock: f64,
+    pub invincible_until: f64,
+    pub last_shot: f64,
+    pub rapid_fire_until: f64,
+    pub magnet_until: f64,
+    pub time_slow_until: f64,
+    pub weapon: Weapon,
+    pub weapon_until: f64,
+    /// Horizontal input of the last update, from -1.0 left to 1.0 right
+    pub direction: f32,
+    /// Whether the fire button is being held down
+    pub charging: bool,
+    /// Seconds the fire button has been held down
+    pub charge_time: f32,
+}
+
+/// What the fire button did during a frame.
+pub enum Trigger {
+    Idle,
+    /// A regular shot with the current weapon
+    Shot,
+    /// A charged shot, with how far it was charged from 0.0 to 1.0
+    ChargedShot(f32),
+}
+
+impl Player {
+    pub fn new(spawn: Vec2) -> Self {
+        Self {
+            size: 32.0,
+            speed: MOVEMENT_SPEED,
+            x: spawn.x,
+            y: spawn.y,
+            vx: 0.0,
+            vy: 0.0,
+            lives: STARTING_LIVES,
+            bombs: STARTING_BOMBS,
+            clock: 0.0,
+            invincible_until: 0.0,
+            last_shot: f64::NEG_INFINITY,
+            rapid_fire_until: 0.0,
+            magnet_until: 0.0,
+            time_slow_until: 0.0,
+            weapon: Weapon::Single,
+            weapon_until: 0.0,
+            direction: 0.0,
+            charging: false,
+            charge_time: 0.0,
+        }
+    }
+
+    /// Puts the player back in the center after losing a life.
+    pub fn respawn(&mut self) {
+        self.x = screen_width() / 2.0;
+        self.y = screen_height() / 2.0;
+        self.vx = 0.0;
+        self.vy = 0.0;
+        self.invincible_until = self.clock + RESPAWN_INVINCIBILITY;
+    }
+
+    /// Shoves the player away from `from` after a hit that doesn't cost a
+    /// life, blinking for a moment so the same enemy can't hit again.
+    pub fn knock_back(&mut self, from: Vec2) {
+        let away = (vec2(self.x, self.y) - from)
+            .try_normalize()
+            .unwrap_or(vec2(0.0, 1.0));
+        self.x += away.x * KNOCK_BACK_DISTANCE;
+        self.y += away.y * KNOCK_BACK_DISTANCE;
+        self.vx = 0.0;
+        self.vy = 0.0;
+        self.clamp_to_screen();
+        self.invincible_until = self.clock + KNOCK_BACK_INVINCIBILITY;
+    }
+
+    pub fn is_invincible(&self) -> bool {
+        self.clock < self.invincible_until
+    }
+
+    pub fn has_rapid_fire(&self) -> bool {
+        self.clock < self.rapid_fire_until
+    }
+
+    pub fn has_magnet(&self) -> bool {
+        self.clock < self.magnet_until
+    }
+
+    pub fn has_time_slow(&self) -> bool {
+        self.clock < self.time_slow_until
+    }
+
+    pub fn fire_cooldown(&self) -> f64 {
+        if self.has_rapid_fire() {
+            FIRE_COOLDOWN / 2.0
+        } else {
+            FIRE_COOLDOWN
+        }
+    }
+
+    /// Starts the cooldown and returns true if the player may fire right now.
+    pub fn try_fire(&mut self) -> bool {
+        if self.clock - self.last_shot < self.fire_cooldown() {
+            return false;
+        }
+        self.last_shot = self.clock;
+        true
+    }
+
+    /// How far the held shot has charged, from 0.0 to 1.0.
+    pub fn charge(&self) -> f32 {
+        ((self.charge_time - CHARGE_DELAY) / (FULL_CHARGE_TIME - CHARGE_DELAY)).clamp(0.0, 1.0)
+    }
+
+    /// Handles the fire button for one frame. Pressing it fires a regular
+    /// shot, holding it charges a stronger one that is let go on release.
+    /// With rapid fire, holding it keeps firing regular shots instead.
+    pub fn pull_trigger(&mut self, held: bool, dt: f32) -> Trigger {
+        if self.has_rapid_fire() {
+            self.charging = false;
+            return if held && self.try_fire() {
+                Trigger::Shot
+            } else {
+                Trigger::Idle
+            };
+        }
+        if held && !self.charging {
+            self.charging = true;
+            self.charge_time = 0.0;
+            return if self.try_fire() {
+                Trigger::Shot
+            } else {
+                Trigger::Idle
+            };
+        }
+        if held {
+            self.charge_time += dt;
+            return Trigger::Idle;
+        }
+        if self.charging {
+            self.charging = false;
+            if self.charge_time >= CHARGE_DELAY {
+                self.last_shot = self.clock;
+                return Trigger::ChargedShot(self.charge());
+            }
+        }
+        Trigger::Idle
+    }
+
+    pub fn apply_power_up(&mut self, kind: PowerUpKind) {
+        let until = self.clock + kind.duration();
+        match kind {
+            PowerUpKind::RapidFire => self.rapid_fire_until = until,
+            PowerUpKind::Spread => {
+                self.weapon = Weapon::Spread;
+                self.weapon_until = until;
+            }
+            PowerUpKind::Magnet => self.magnet_until = until,
+            PowerUpKind::TimeSlow => self.time_slow_until = until,
+        }
+    }
+
+    /// Returns the running power-ups along with the fraction of time they have left.
+    pub fn active_power_ups(&self) -> Vec<(PowerUpKind, f32)> {
+        let mut active = vec![];
+        if self.has_rapid_fire() {
+            active.push((PowerUpKind::RapidFire, self.rapid_fire_until));
+        }
+        if self.weapon == Weapon::Spread {
+            active.push((PowerUpKind::Spread, self.weapon_until));
+        }
+        if self.has_magnet() {
+            active.push((PowerUpKind::Magnet, self.magnet_until));
+        }
+        if self.has_time_slow() {
+            active.push((PowerUpKind::TimeSlow, self.time_slow_until));
+        }
+        active
+            .into_iter()
+            .map(|(kind, until)| (kind, ((until - self.clock) / kind.duration()) as f32))
+            .collect()
+    }
+
+    /// Advances the clock and moves the player along `movement`, whose
+    /// components are each between -1.0 and 1.0. With `inertia` the movement
+    /// accelerates the ship and friction slows it down, instead of setting
+    /// its velocity outright.
+    pub fn update(&mut self, dt: f32, movement: Vec2, inertia: bool) {
+        self.clock += dt as f64;
+        if self.weapon != Weapon::Single && self.clock >= self.weapon_until {
+            self.weapon = Weapon::Single;
+        }
+        self.direction = movement.x;
+        let velocity = if inertia {
+            let velocity = vec2(self.vx, self.vy) + movement * INERTIA_ACCELERATION * dt;
+            (velocity * (1.0 - INERTIA_FRICTION * dt).max(0.0))
+                .clamp_length_max(self.speed * INERTIA_MAX_SPEED_SCALE)
+        } else {
+            movement * self.speed
+        };
+        self.vx = velocity.x;
+        self.vy = velocity.y;
+        self.x += self.vx * dt;
+        self.y += self.vy * dt;
+
+        self.clamp_to_screen();
+    }
+
+    /// Clamps X and Y to be within the screen, stopping the ship against
+    /// the edge it ran into so it doesn't stick there.
+    pub fn clamp_to_screen(&mut self) {
+        let x = self.x.min(screen_width()).max(0.0);
+        let y = self.y.min(screen_height()).max(0.0);
+        if x != self.x {
+            self.vx = 0.0;
+        }
+        if y != self.y {
+            self.vy = 0.0;
+        }
+        self.x = x;
+        self.y = y;
+    }
+
+    /// The area around the ship where enemies and bullets count as a near
+    /// miss.
+    pub fn graze_rect(&self) -> Rect {
+        let size = self.size + GRAZE_MARGIN * 2.0;
+        Rect::new(self.x - size / 2.0, self.y - size / 2.0, size, size)
+    }
+}
+
+impl Collider for Player {
+    fn rect(&self) -> Rect {
+        Rect {
+            x: self.x - self.size / 2.0,
+            y: self.y - self.size / 2.0,
+            w: self.size,
+            h: self.size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape_at(x: f32, y: f32, size: f32) -> Shape {
+        Shape {
+            size,
+            x,
+            y,
+            vx: 0.0,
+            vy: 0.0,
+            collided: false,
+            grazed: false,
+            damage: 1,
+            pierce_count: 0,
+            pierced: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn bullet_pool_reuses_free_slots() {
+        let mut pool = BulletPool::new();
+        for x in [10.0, 20.0, 30.0] {
+            pool.spawn(shape_at(x, 0.0, 8.0));
+        }
+        pool.retain(|bullet| bullet.x != 20.0);
+        assert_eq!(pool.iter().count(), 2);
+
+        pool.spawn(shape_at(40.0, 0.0, 8.0));
+        assert_eq!(pool.slots.len(), 3);
+        assert_eq!(pool.slots[1].bullet.x, 40.0);
+        assert_eq!(pool.iter().count(), 3);
+    }
+
+    #[test]
+    fn bullet_pool_doesnt_grow_past_the_most_bullets_in_flight() {
+        let mut pool = BulletPool::new();
+        for _ in 0..10 {
+            for x in [10.0, 20.0, 30.0, 40.0, 50.0] {
+                pool.spawn(shape_at(x, 0.0, 8.0));
+            }
+            pool.retain(|_| false);
+        }
+        assert_eq!(pool.slots.len(), 5);
+        assert_eq!(pool.iter().count(), 0);
+
+        pool.clear();
+        for x in [10.0, 20.0, 30.0] {
+            pool.spawn(shape_at(x, 0.0, 8.0));
+        }
+        assert_eq!(pool.slots.len(), 5);
+    }
+
+    #[test]
+    fn overlapping_shapes_collide() {
+        let a = shape_at(100.0, 100.0, 32.0);
+        let b = shape_at(120.0, 110.0, 16.0);
+        assert!(a.collides_with(&b));
+        assert!(b.collides_with(&a));
+        // One inside the other
+        assert!(a.collides_with(&shape_at(100.0, 100.0, 4.0)));
+    }
+
+    #[test]
+    fn shapes_touching_at_an_edge_collide() {
+        let a = shape_at(100.0, 100.0, 32.0);
+        // Side by side, and corner to corner
+        assert!(a.collides_with(&shape_at(132.0, 100.0, 32.0)));
+        assert!(a.collides_with(&shape_at(100.0, 68.0, 32.0)));
+        assert!(a.collides_with(&shape_at(132.0, 132.0, 32.0)));
+    }
+
+    #[test]
+    fn disjoint_shapes_dont_collide() {
+        let a = shape_at(100.0, 100.0, 32.0);
+        assert!(!a.collides_with(&shape_at(133.0, 100.0, 32.0)));
+        assert!(!a.collides_with(&shape_at(100.0, 140.0, 8.0)));
+        // Lined up on one axis but apart on the other
+        assert!(!a.collides_with(&shape_at(200.0, 100.0, 32.0)));
+        assert!(!a.collides_with(&shape_at(100.0, 200.0, 32.0)));
+    }
+}
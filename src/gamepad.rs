@@ -0,0 +1,137 @@
+#[cfg(not(target_arch = "wasm32"))]
+use gilrs::{Axis, EventType, Gilrs};
+use macroquad::prelude::*;
+
+/// How far the left stick has to be pushed before the ship moves, so a
+/// worn stick resting slightly off center doesn't make it drift
+#[cfg(not(target_arch = "wasm32"))]
+const STICK_DEAD_ZONE: f32 = 0.2;
+
+/// The gamepad buttons the game listens to, named by where they sit on the
+/// pad rather than by their label, which differs between brands.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Button {
+    South,
+    East,
+    Start,
+    Select,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Button {
+    fn to_gilrs(self) -> gilrs::Button {
+        match self {
+            Button::South => gilrs::Button::South,
+            Button::East => gilrs::Button::East,
+            Button::Start => gilrs::Button::Start,
+            Button::Select => gilrs::Button::Select,
+            Button::DPadUp => gilrs::Button::DPadUp,
+            Button::DPadDown => gilrs::Button::DPadDown,
+            Button::DPadLeft => gilrs::Button::DPadLeft,
+            Button::DPadRight => gilrs::Button::DPadRight,
+        }
+    }
+}
+
+/// The connected gamepads, read alongside the keyboard. With no gamepad
+/// plugged in, or none supported on the platform, every button reads as
+/// released and the stick as centered. The browser build has no gamepad
+/// support at all.
+pub struct Gamepads {
+    #[cfg(not(target_arch = "wasm32"))]
+    gilrs: Option<Gilrs>,
+    /// Buttons pressed on any gamepad since the last frame
+    #[cfg(not(target_arch = "wasm32"))]
+    pressed: Vec<gilrs::Button>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Gamepads {
+    pub fn new() -> Self {
+        let gilrs = Gilrs::new()
+            .map_err(|err| eprintln!("Warning: gamepads aren't available ({})", err))
+            .ok();
+        Self {
+            gilrs,
+            pressed: Vec::new(),
+        }
+    }
+
+    /// Picks up what happened on the gamepads since the last frame. Called
+    /// once at the start of every frame.
+    pub fn update(&mut self) {
+        self.pressed.clear();
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        while let Some(event) = gilrs.next_event() {
+            if let EventType::ButtonPressed(button, _) = event.event {
+                self.pressed.push(button);
+            }
+        }
+    }
+
+    /// Whether the button was pressed on any gamepad this frame.
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button.to_gilrs())
+    }
+
+    /// Whether the button is held on any gamepad.
+    pub fn is_down(&self, button: Button) -> bool {
+        self.gilrs.as_ref().is_some_and(|gilrs| {
+            gilrs
+                .gamepads()
+                .any(|(_, gamepad)| gamepad.is_pressed(button.to_gilrs()))
+        })
+    }
+
+    /// Where the left stick of the first gamepad that is pushed points, or
+    /// the d-pad if no stick is, with each component from -1.0 to 1.0 and
+    /// down being positive like on the screen.
+    pub fn movement(&self) -> Vec2 {
+        let Some(gilrs) = &self.gilrs else {
+            return Vec2::ZERO;
+        };
+        for (_, gamepad) in gilrs.gamepads() {
+            let stick = vec2(
+                gamepad.value(Axis::LeftStickX),
+                -gamepad.value(Axis::LeftStickY),
+            );
+            if stick.length() > STICK_DEAD_ZONE {
+                return stick.clamp_length_max(1.0);
+            }
+        }
+        let axis = |negative: Button, positive: Button| {
+            self.is_down(positive) as i32 as f32 - self.is_down(negative) as i32 as f32
+        };
+        vec2(
+            axis(Button::DPadLeft, Button::DPadRight),
+            axis(Button::DPadUp, Button::DPadDown),
+        )
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Gamepads {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn update(&mut self) {}
+
+    pub fn is_pressed(&self, _button: Button) -> bool {
+        false
+    }
+
+    pub fn is_down(&self, _button: Button) -> bool {
+        false
+    }
+
+    pub fn movement(&self) -> Vec2 {
+        Vec2::ZERO
+    }
+}
@@ -0,0 +1,240 @@
+use macroquad::input::{is_key_down, KeyCode};
+use serde::{Deserialize, Serialize};
+
+use crate::language::Text;
+
+/// Keys an action can be bound to. Keys the game already uses for something
+/// else, like M for muting and WASD for moving, are left out.
+const BINDABLE_KEYS: [KeyCode; 42] = [
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::LeftShift,
+    KeyCode::RightShift,
+    KeyCode::LeftControl,
+    KeyCode::RightControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightAlt,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::E,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// Whether `key` can be bound to any action.
+fn is_bindable(key: KeyCode) -> bool {
+    BINDABLE_KEYS.contains(&key)
+}
+
+/// The name a key is shown and saved under.
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+/// Something the player does with a key during a run.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Pause,
+    Bomb,
+    Restart,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Pause,
+        Action::Bomb,
+        Action::Restart,
+    ];
+
+    pub fn label(self) -> Text {
+        match self {
+            Action::MoveUp => Text::MoveUp,
+            Action::MoveDown => Text::MoveDown,
+            Action::MoveLeft => Text::MoveLeft,
+            Action::MoveRight => Text::MoveRight,
+            Action::Fire => Text::FireControl,
+            Action::Pause => Text::PauseControl,
+            Action::Bomb => Text::BombControl,
+            Action::Restart => Text::RestartControl,
+        }
+    }
+
+    /// Whether `key` can be bound to this action, leaving out the keys a
+    /// screen that reads the action already uses for something else, which
+    /// would be checked before the binding there.
+    pub fn can_bind(self, key: KeyCode) -> bool {
+        let reserved: &[KeyCode] = match self {
+            // Space takes the game over screen back to the menu
+            Action::Restart => &[KeyCode::Space],
+            _ => &[],
+        };
+        is_bindable(key) && !reserved.contains(&key)
+    }
+
+    /// A key that does the same as the bound one no matter the bindings, so
+    /// WASD always moves the ship.
+    fn alternate_key(self) -> Option<KeyCode> {
+        match self {
+            Action::MoveUp => Some(KeyCode::W),
+            Action::MoveDown => Some(KeyCode::S),
+            Action::MoveLeft => Some(KeyCode::A),
+            Action::MoveRight => Some(KeyCode::D),
+            Action::Fire | Action::Pause | Action::Bomb | Action::Restart => None,
+        }
+    }
+}
+
+/// Which key does what during a run. Saved in the settings file with the
+/// keys written out by name.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    #[serde(with = "by_name")]
+    pub move_up: KeyCode,
+    #[serde(with = "by_name")]
+    pub move_down: KeyCode,
+    #[serde(with = "by_name")]
+    pub move_left: KeyCode,
+    #[serde(with = "by_name")]
+    pub move_right: KeyCode,
+    /// Only used with the keyboard control scheme
+    #[serde(with = "by_name")]
+    pub fire: KeyCode,
+    #[serde(with = "by_name")]
+    pub pause: KeyCode,
+    #[serde(with = "by_name")]
+    pub bomb: KeyCode,
+    #[serde(with = "by_name")]
+    pub restart: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            move_up: KeyCode::Up,
+            move_down: KeyCode::Down,
+            move_left: KeyCode::Left,
+            move_right: KeyCode::Right,
+            fire: KeyCode::Space,
+            pause: KeyCode::Escape,
+            bomb: KeyCode::B,
+            restart: KeyCode::R,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: Action) -> KeyCode {
+        match action {
+            Action::MoveUp => self.move_up,
+            Action::MoveDown => self.move_down,
+            Action::MoveLeft => self.move_left,
+            Action::MoveRight => self.move_right,
+            Action::Fire => self.fire,
+            Action::Pause => self.pause,
+            Action::Bomb => self.bomb,
+            Action::Restart => self.restart,
+        }
+    }
+
+    /// Whether the key bound to `action`, or its fixed alternate, is held.
+    pub fn is_down(&self, action: Action) -> bool {
+        is_key_down(self.key(action)) || action.alternate_key().is_some_and(is_key_down)
+    }
+
+    fn key_mut(&mut self, action: Action) -> &mut KeyCode {
+        match action {
+            Action::MoveUp => &mut self.move_up,
+            Action::MoveDown => &mut self.move_down,
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::Fire => &mut self.fire,
+            Action::Pause => &mut self.pause,
+            Action::Bomb => &mut self.bomb,
+            Action::Restart => &mut self.restart,
+        }
+    }
+
+    /// Binds `key` to `action`, unless another action already has it, in
+    /// which case that action is returned and nothing changes.
+    pub fn bind(&mut self, action: Action, key: KeyCode) -> Result<(), Action> {
+        if let Some(other) = Action::ALL
+            .into_iter()
+            .find(|&other| other != action && self.key(other) == key)
+        {
+            return Err(other);
+        }
+        *self.key_mut(action) = key;
+        Ok(())
+    }
+
+    /// Whether every action has a key of its own that it can be bound to.
+    pub fn is_valid(&self) -> bool {
+        Action::ALL.iter().enumerate().all(|(i, &action)| {
+            action.can_bind(self.key(action))
+                && Action::ALL[i + 1..]
+                    .iter()
+                    .all(|&other| self.key(other) != self.key(action))
+        })
+    }
+}
+
+/// Saves a key by its name, so the settings file stays readable.
+mod by_name {
+    use macroquad::input::KeyCode;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{key_name, BINDABLE_KEYS};
+
+    pub fn serialize<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&key_name(*key))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        BINDABLE_KEYS
+            .into_iter()
+            .find(|&key| key_name(key) == name)
+            .ok_or_else(|| D::Error::custom(format!("unknown key {}", name)))
+    }
+}
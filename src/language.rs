@@ -0,0 +1,370 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    Swedish,
+    English,
+}
+
+/// The pieces of text shown in the game, looked up in the current language
+/// with `Language::tr`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Text {
+    PressSpace,
+    LeaderboardHint,
+    MuteHint,
+    UnmuteHint,
+    SettingsHint,
+    ReplayHint,
+    Replay,
+    ScreenshotSaved,
+    ScreenshotFailed,
+    Paused,
+    GameOver,
+    NewRecord,
+    RestartHint,
+    Go,
+    EnterName,
+    Leaderboard,
+    NoResults,
+    Settings,
+    SettingsHelp,
+    Start,
+    Quit,
+    Back,
+    PlayAgain,
+    Resume,
+    Restart,
+    QuitToMenu,
+    Menu,
+    Score,
+    Bombs,
+    HighScore,
+    Stage,
+    Wave,
+    WaveCleared,
+    WaveReached,
+    GameMode,
+    Endless,
+    Waves,
+    TimeAttack,
+    Practice,
+    TimeUp,
+    LeaderboardTabsHint,
+    Enemies,
+    Bullets,
+    Explosions,
+    On,
+    Off,
+    Easy,
+    Normal,
+    Hard,
+    Audio,
+    MusicVolume,
+    SfxVolume,
+    Difficulty,
+    Controls,
+    Inertia,
+    Keyboard,
+    Mouse,
+    Seed,
+    RandomSeed,
+    DailySeed,
+    RecordReplay,
+    ScreenShake,
+    ReduceMotion,
+    StarScroll,
+    CrtEffect,
+    Theme,
+    Classic,
+    Neon,
+    Monochrome,
+    Colorblind,
+    MoveControl,
+    FireControl,
+    PauseControl,
+    BombControl,
+    RestartControl,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Keys,
+    KeysHelp,
+    PressKey,
+    KeyInUse,
+    KeyNotAllowed,
+    ShotsFired,
+    ShotsHit,
+    Accuracy,
+    EnemiesDestroyed,
+    TimeSurvived,
+    MaxCombo,
+    Stats,
+    GamesPlayed,
+    TimePlayed,
+    Achievements,
+    AchievementUnlocked,
+    Destroy100Enemies,
+    Combo10,
+    Survive5Minutes,
+    NewHighScore,
+    ExtraLife,
+    Warning,
+    RapidFire,
+    Spread,
+    Magnet,
+    TimeSlow,
+    ShowFps,
+    FrameCap,
+    Unlimited,
+    Language,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::Swedish, Language::English];
+
+    /// The name of the language, written in that language.
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::Swedish => "Svenska",
+            Language::English => "English",
+        }
+    }
+
+    pub fn tr(self, text: Text) -> &'static str {
+        match self {
+            Language::Swedish => swedish(text),
+            Language::English => english(text),
+        }
+    }
+}
+
+fn swedish(text: Text) -> &'static str {
+    match text {
+        Text::PressSpace => "Tryck på mellanslag",
+        Text::LeaderboardHint => "L: topplista",
+        Text::MuteHint => "M: stäng av ljudet",
+        Text::UnmuteHint => "M: sätt på ljudet",
+        Text::SettingsHint => "S: inställningar",
+        Text::ReplayHint => "V: se senaste repris",
+        Text::Replay => "Repris",
+        Text::ScreenshotSaved => "Skärmdump sparad",
+        Text::ScreenshotFailed => "Kunde inte spara skärmdumpen",
+        Text::Paused => "Pausad",
+        Text::GameOver => "Game Over!",
+        Text::NewRecord => "Nytt rekord!",
+        Text::RestartHint => "spela igen",
+        Text::Go => "Kör!",
+        Text::EnterName => "Skriv ditt namn",
+        Text::Leaderboard => "Topplista",
+        Text::NoResults => "Inga resultat än",
+        Text::Settings => "Inställningar",
+        Text::SettingsHelp => "Upp/ner: välj  Vänster/höger: ändra  Esc: tillbaka",
+        Text::Start => "Starta",
+        Text::Quit => "Avsluta",
+        Text::Back => "Tillbaka",
+        Text::PlayAgain => "Spela igen",
+        Text::Resume => "Fortsätt",
+        Text::Restart => "Börja om",
+        Text::QuitToMenu => "Till menyn",
+        Text::Menu => "Meny",
+        Text::Score => "Poäng",
+        Text::Bombs => "Bomber",
+        Text::HighScore => "High score",
+        Text::Stage => "Nivå",
+        Text::Wave => "Våg",
+        Text::WaveCleared => "Vågen avklarad",
+        Text::WaveReached => "Nådd våg",
+        Text::GameMode => "Läge",
+        Text::Endless => "Ändlöst",
+        Text::Waves => "Vågor",
+        Text::TimeAttack => "Tidsjakt",
+        Text::Practice => "Träning",
+        Text::TimeUp => "Tiden är ute!",
+        Text::LeaderboardTabsHint => "Vänster/höger: läge   Upp/ner: svårighetsgrad",
+        Text::Enemies => "Fiender",
+        Text::Bullets => "Skott",
+        Text::Explosions => "Explosioner",
+        Text::On => "På",
+        Text::Off => "Av",
+        Text::Easy => "Lätt",
+        Text::Normal => "Normal",
+        Text::Hard => "Svår",
+        Text::Audio => "Ljud",
+        Text::MusicVolume => "Musikvolym",
+        Text::SfxVolume => "Effektvolym",
+        Text::Difficulty => "Svårighetsgrad",
+        Text::Controls => "Styrning",
+        Text::Inertia => "Tröghet",
+        Text::Keyboard => "Tangentbord",
+        Text::Mouse => "Mus",
+        Text::Seed => "Seed",
+        Text::RandomSeed => "Slumpad",
+        Text::DailySeed => "Dagens",
+        Text::RecordReplay => "Spela in repris",
+        Text::ScreenShake => "Skakning",
+        Text::ReduceMotion => "Mindre rörelse",
+        Text::StarScroll => "Stjärnornas lutning",
+        Text::CrtEffect => "CRT-effekt",
+        Text::Theme => "Färgtema",
+        Text::Classic => "Klassiskt",
+        Text::Neon => "Neon",
+        Text::Monochrome => "Svartvitt",
+        Text::Colorblind => "Färgblindläge",
+        Text::MoveControl => "flytta",
+        Text::FireControl => "skjut",
+        Text::PauseControl => "paus",
+        Text::BombControl => "bomb",
+        Text::RestartControl => "börja om",
+        Text::MoveUp => "upp",
+        Text::MoveDown => "ner",
+        Text::MoveLeft => "vänster",
+        Text::MoveRight => "höger",
+        Text::Keys => "Tangenter",
+        Text::KeysHelp => "Upp/ner: välj  Enter: byt tangent  Esc: tillbaka",
+        Text::PressKey => "Tryck på den nya tangenten  Backsteg: avbryt",
+        Text::KeyInUse => "Tangenten används redan till",
+        Text::KeyNotAllowed => "Den tangenten går inte att använda",
+        Text::ShotsFired => "Skott",
+        Text::ShotsHit => "Träffar",
+        Text::Accuracy => "Träffsäkerhet",
+        Text::EnemiesDestroyed => "Förstörda fiender",
+        Text::TimeSurvived => "Överlevd tid",
+        Text::MaxCombo => "Högsta kombo",
+        Text::Stats => "Statistik",
+        Text::GamesPlayed => "Spelade omgångar",
+        Text::TimePlayed => "Speltid",
+        Text::Achievements => "Prestationer",
+        Text::AchievementUnlocked => "Ny prestation",
+        Text::Destroy100Enemies => "Förstör 100 fiender",
+        Text::Combo10 => "Nå en kombo på x10",
+        Text::Survive5Minutes => "Överlev i 5 minuter",
+        Text::NewHighScore => "Slå ditt rekord",
+        Text::ExtraLife => "Extra liv!",
+        Text::Warning => "VARNING",
+        Text::RapidFire => "Snabbeld",
+        Text::Spread => "Spridda skott",
+        Text::Magnet => "Magnet",
+        Text::TimeSlow => "Ultrarapid",
+        Text::ShowFps => "Visa FPS",
+        Text::FrameCap => "Max FPS",
+        Text::Unlimited => "Obegränsad",
+        Text::Language => "Språk",
+    }
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::PressSpace => "Press space",
+        Text::LeaderboardHint => "L: leaderboard",
+        Text::MuteHint => "M: mute sound",
+        Text::UnmuteHint => "M: unmute sound",
+        Text::SettingsHint => "S: settings",
+        Text::ReplayHint => "V: watch last replay",
+        Text::Replay => "Replay",
+        Text::ScreenshotSaved => "Saved screenshot",
+        Text::ScreenshotFailed => "Couldn't save screenshot",
+        Text::Paused => "Paused",
+        Text::GameOver => "Game Over!",
+        Text::NewRecord => "New record!",
+        Text::RestartHint => "play again",
+        Text::Go => "Go!",
+        Text::EnterName => "Enter your name",
+        Text::Leaderboard => "Leaderboard",
+        Text::NoResults => "No scores yet",
+        Text::Settings => "Settings",
+        Text::SettingsHelp => "Up/down: select  Left/right: change  Esc: back",
+        Text::Start => "Start",
+        Text::Quit => "Quit",
+        Text::Back => "Back",
+        Text::PlayAgain => "Play again",
+        Text::Resume => "Resume",
+        Text::Restart => "Restart",
+        Text::QuitToMenu => "Quit to menu",
+        Text::Menu => "Menu",
+        Text::Score => "Score",
+        Text::Bombs => "Bombs",
+        Text::HighScore => "High score",
+        Text::Stage => "Stage",
+        Text::Wave => "Wave",
+        Text::WaveCleared => "Wave cleared",
+        Text::WaveReached => "Wave reached",
+        Text::GameMode => "Mode",
+        Text::Endless => "Endless",
+        Text::Waves => "Waves",
+        Text::TimeAttack => "Time attack",
+        Text::Practice => "Practice",
+        Text::TimeUp => "Time's up!",
+        Text::LeaderboardTabsHint => "Left/right: mode   Up/down: difficulty",
+        Text::Enemies => "Enemies",
+        Text::Bullets => "Bullets",
+        Text::Explosions => "Explosions",
+        Text::On => "On",
+        Text::Off => "Off",
+        Text::Easy => "Easy",
+        Text::Normal => "Normal",
+        Text::Hard => "Hard",
+        Text::Audio => "Sound",
+        Text::MusicVolume => "Music volume",
+        Text::SfxVolume => "Effects volume",
+        Text::Difficulty => "Difficulty",
+        Text::Controls => "Controls",
+        Text::Inertia => "Inertia",
+        Text::Keyboard => "Keyboard",
+        Text::Mouse => "Mouse",
+        Text::Seed => "Seed",
+        Text::RandomSeed => "Random",
+        Text::DailySeed => "Daily",
+        Text::RecordReplay => "Record replay",
+        Text::ScreenShake => "Screen shake",
+        Text::ReduceMotion => "Reduce motion",
+        Text::StarScroll => "Star lean",
+        Text::CrtEffect => "CRT effect",
+        Text::Theme => "Color theme",
+        Text::Classic => "Classic",
+        Text::Neon => "Neon",
+        Text::Monochrome => "Monochrome",
+        Text::Colorblind => "Colorblind mode",
+        Text::MoveControl => "move",
+        Text::FireControl => "shoot",
+        Text::PauseControl => "pause",
+        Text::BombControl => "bomb",
+        Text::RestartControl => "restart",
+        Text::MoveUp => "up",
+        Text::MoveDown => "down",
+        Text::MoveLeft => "left",
+        Text::MoveRight => "right",
+        Text::Keys => "Keys",
+        Text::KeysHelp => "Up/down: select  Enter: change key  Esc: back",
+        Text::PressKey => "Press the new key  Backspace: cancel",
+        Text::KeyInUse => "That key is already used for",
+        Text::KeyNotAllowed => "That key can't be used",
+        Text::ShotsFired => "Shots fired",
+        Text::ShotsHit => "Shots hit",
+        Text::Accuracy => "Accuracy",
+        Text::EnemiesDestroyed => "Enemies destroyed",
+        Text::TimeSurvived => "Time survived",
+        Text::MaxCombo => "Best combo",
+        Text::Stats => "Stats",
+        Text::GamesPlayed => "Games played",
+        Text::TimePlayed => "Time played",
+        Text::Achievements => "Achievements",
+        Text::AchievementUnlocked => "Achievement unlocked",
+        Text::Destroy100Enemies => "Destroy 100 enemies",
+        Text::Combo10 => "Reach a x10 combo",
+        Text::Survive5Minutes => "Survive 5 minutes",
+        Text::NewHighScore => "Beat your high score",
+        Text::ExtraLife => "Extra life!",
+        Text::Warning => "WARNING",
+        Text::RapidFire => "Rapid fire",
+        Text::Spread => "Spread shot",
+        Text::Magnet => "Magnet",
+        Text::TimeSlow => "Slow motion",
+        Text::ShowFps => "Show FPS",
+        Text::FrameCap => "Frame rate cap",
+        Text::Unlimited => "Unlimited",
+        Text::Language => "Language",
+    }
+}
@@ -1,448 +1,151 @@
-use macroquad::experimental::animation::{AnimatedSprite, Animation};
 use macroquad::prelude::*;
-use macroquad_particles::{self as particles, AtlasConfig, Emitter, EmitterConfig};
-use macroquad::audio::{load_sound, play_sound, play_sound_once, PlaySoundParams};
 
-use std::fs;
-
-const FRAGMENT_SHADER: &str = include_str!("starfield-shader.glsl");
-
-const VERTEX_SHADER: &str = "#version 100
-attribute vec3 position;
-attribute vec2 texcoord;
-attribute vec4 color0;
-varying float iTime;
-
-uniform mat4 Model;
-uniform mat4 Projection;
-uniform vec4 _Time;
-
-void main() {
-    gl_Position = Projection * Model * vec4(position, 1);
-    iTime = _Time.x;
-}
-";
-
-struct Shape {
-    size: f32,
-    speed: f32,
-    x: f32,
-    y: f32,
-    collided: bool,
-}
-
-impl Shape {
-    fn collides_with(&self, other: &Self) -> bool {
-        self.rect().overlaps(&other.rect())
-    }
-
-    fn rect(&self) -> Rect {
-        Rect {
-            x: self.x - self.size / 2.0,
-            y: self.y - self.size / 2.0,
-            w: self.size,
-            h: self.size,
-        }
-    }
-}
-
-enum GameState {
-    MainMenu,
-    Playing,
-    Paused,
-    GameOver,
-}
-
-fn particle_explosion() -> particles::EmitterConfig {
-    particles::EmitterConfig {
-        local_coords: false,
-        one_shot: true,
-        emitting: true,
-        lifetime: 0.6,
-        lifetime_randomness: 0.3,
-        explosiveness: 0.65,
-        initial_direction_spread: 2.0 * std::f32::consts::PI,
-        initial_velocity: 400.0,
-        initial_velocity_randomness: 0.8,
-        size: 16.0,
-        size_randomness: 0.3,
-        atlas: Some(AtlasConfig::new(5, 1, 0..)),
+mod achievements;
+mod audio;
+mod collision;
+mod entities;
+mod gamepad;
+mod key_bindings;
+mod language;
+mod palette;
+mod render;
+mod replay;
+mod settings;
+mod state;
+mod stats;
+mod storage;
+mod waves;
+
+use language::Text;
+use render::{draw_debug_overlay, draw_toasts, save_screenshot, FpsCounter, Starfield};
+use settings::Settings;
+use state::{GameContext, GameState, Transition};
+
+/// Smallest window that is opened, in case the saved size is unusable
+const MIN_WINDOW_SIZE: (u32, u32) = (320, 240);
+/// The end of a capped frame is waited out in a busy loop rather than slept,
+/// since sleeping can overshoot by about this many seconds
+#[cfg(not(target_arch = "wasm32"))]
+const SPIN_WAIT_TIME: f32 = 0.001;
+
+/// Opens the window at the size and mode it had last time, rendering at the
+/// display's full resolution on high-DPI screens.
+fn window_conf() -> Conf {
+    let settings = Settings::load();
+    Conf {
+        window_title: "Mitt spel".to_owned(),
+        window_width: settings.window_width.max(MIN_WINDOW_SIZE.0) as i32,
+        window_height: settings.window_height.max(MIN_WINDOW_SIZE.1) as i32,
+        high_dpi: true,
+        fullscreen: settings.fullscreen,
         ..Default::default()
     }
 }
 
-#[macroquad::main("Mitt spel")]
+#[macroquad::main(window_conf)]
 async fn main() {
-    const MOVEMENT_SPEED: f32 = 200.0;
-
     rand::srand(miniquad::date::now() as u64);
-    let theme_music = load_sound("assets/8bit-spaceshooter.ogg").await.unwrap();
-    let sound_explosion = load_sound("assets/explosion.wav").await.unwrap();
-    let sound_laser = load_sound("assets/laser.wav").await.unwrap();
-    let sound_hit = load_sound("assets/hit.wav").await.unwrap();
-    let mut squares = vec![];
-    let mut bullets: Vec<Shape> = vec![];
-    let mut circle = Shape {
-        size: 32.0,
-        speed: MOVEMENT_SPEED,
-        x: screen_width() / 2.0,
-        y: screen_height() / 2.0,
-        collided: false,
-    };
-    let mut score: u32 = 0;
-    let mut high_score: u32 = fs::read_to_string("highscore.dat")
-        .map_or(Ok(0), |i| i.parse::<u32>())
-        .unwrap_or(0);
+    let mut ctx = GameContext::new().await;
+    let mut starfield = Starfield::new();
+    let mut fps_counter = FpsCounter::new();
     let mut game_state = GameState::MainMenu;
-    let mut enemy_small_sprite = AnimatedSprite::new(
-        17,
-        16,
-        &[Animation {
-            name: "enemy_small".to_string(),
-            row: 0,
-            frames: 2,
-            fps: 12,
-        }],
-        true,
-    );
-    let mut direction_modifier: f32 = 0.0;
-    let render_target = render_target(320, 150);
-    render_target.texture.set_filter(FilterMode::Nearest);
-    let material = load_material(
-        ShaderSource::Glsl {
-            vertex: VERTEX_SHADER,
-            fragment: FRAGMENT_SHADER,
-        },
-        MaterialParams {
-            uniforms: vec![
-                ("iResolution".to_owned(), UniformType::Float2),
-                ("direction_modifier".to_owned(), UniformType::Float1),
-            ],
-            ..Default::default()
-        },
-    )
-    .unwrap();
-
-    let mut explosions: Vec<(Emitter, Vec2)> = vec![];
-
-    set_pc_assets_folder("assets");
-    let ship_texture: Texture2D = load_texture("ship.png").await.expect("Couldn't load file");
-    ship_texture.set_filter(FilterMode::Nearest);
-    let bullet_texture: Texture2D = load_texture("laser-bolts.png")
-        .await
-        .expect("Couldn't load file");
-    bullet_texture.set_filter(FilterMode::Nearest);
-    let explosions_texture: Texture2D = load_texture("explosion.png")
-        .await
-        .expect("Couldn't lead file");
-    explosions_texture.set_filter(FilterMode::Nearest);
-
-    let enemy_small_texture: Texture2D = load_texture("enemy-small.png")
-        .await
-        .expect("Couldn't load file");
-    enemy_small_texture.set_filter(FilterMode::Nearest);
-    build_textures_atlas();
-
-    let mut ship_sprite = AnimatedSprite::new(
-        16,
-        24,
-        &[
-            Animation {
-                name: "idle".to_string(),
-                row: 0,
-                frames: 2,
-                fps: 12,
-            },
-            Animation {
-                name: "left".to_string(),
-                row: 2,
-                frames: 2,
-                fps: 12,
-            },
-            Animation {
-                name: "left2".to_string(),
-                row: 2,
-                frames: 2,
-                fps: 12,
-            },
-            Animation {
-                name: "right".to_string(),
-                row: 4,
-                frames: 2,
-                fps: 12,
-            },
-            Animation {
-                name: "right2".to_string(),
-                row: 4,
-                frames: 2,
-                fps: 12,
-            },
-        ],
-        true,
-    );
-    let mut bullet_sprite = AnimatedSprite::new(
-        16,
-        16,
-        &[
-            Animation {
-                name: "bullet".to_string(),
-                row: 0,
-                frames: 2,
-                fps: 12,
-            },
-            Animation {
-                name: "bolt".to_string(),
-                row: 1,
-                frames: 2,
-                fps: 12,
-            },
-        ],
-        true,
-    );
-    bullet_sprite.set_animation(1);
-
-    play_sound(
-        &theme_music,
-        PlaySoundParams {
-            looped: true,
-            volume: 0.3,
-        },
-    );
+    let mut screen = game_state.screen();
+    let mut transition = Transition::new();
+    // Seconds slept at the end of each frame to hold the frame rate cap
+    let mut frame_sleep = 0.0;
 
     loop {
-        clear_background(BLACK);
-
-        material.set_uniform("iResolution", (screen_width(), screen_height()));
-        material.set_uniform("direction_modifier", direction_modifier);
-        gl_use_material(&material);
-        draw_texture_ex(
-            &render_target.texture,
-            0.,
-            0.,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(vec2(screen_width(), screen_height())),
-                ..Default::default()
-            },
+        ctx.gamepads.update();
+        ctx.handle_resize();
+        ctx.post_process.begin(ctx.settings.crt_effect);
+        clear_background(ctx.palette().background);
+        starfield.draw(
+            ctx.run.direction_modifier,
+            ctx.run.warp_intensity,
+            ctx.settings.star_scroll_scale,
         );
-        gl_use_default_material();
 
-        match game_state {
-            GameState::MainMenu => {
-                if is_key_pressed(KeyCode::Escape) {
-                    std::process::exit(0);
-                }
-                if is_key_pressed(KeyCode::Space) {
-                    squares.clear();
-                    bullets.clear();
-                    explosions.clear();
-                    circle.x = screen_width() / 2.0;
-                    circle.y = screen_height() / 2.0;
-                    score = 0;
-                    game_state = GameState::Playing;
-                }
-                let text = "Tryck på mellanslag";
-                let text_dimensions = measure_text(text, None, 50, 1.0);
-                draw_text(
-                    text,
-                    screen_width() / 2.0 - text_dimensions.width / 2.0,
-                    screen_height() / 2.0,
-                    50.0,
-                    WHITE,
-                );
-            }
-            GameState::Playing => {
-                let delta_time = get_frame_time();
-                ship_sprite.set_animation(0);
-                if is_key_down(KeyCode::Right) {
-                    circle.x += MOVEMENT_SPEED * delta_time;
-                    direction_modifier += 0.05 * delta_time;
-                    ship_sprite.set_animation(2);
-                    
-                }
-                if is_key_down(KeyCode::Left) {
-                    circle.x -= MOVEMENT_SPEED * delta_time;
-                    direction_modifier -= 0.05 * delta_time;
-                    ship_sprite.set_animation(1);
-                }
-                if is_key_down(KeyCode::Down) {
-                    circle.y += MOVEMENT_SPEED * delta_time;
-                }
-                if is_key_down(KeyCode::Up) {
-                    circle.y -= MOVEMENT_SPEED * delta_time;
-                }
-                if is_key_pressed(KeyCode::Space) {
-                    bullets.push(Shape {
-                        x: circle.x,
-                        y: circle.y - 24.0,
-                        speed: circle.speed * 2.0,
-                        size: 32.0,
-                        collided: false,
-                    });
-                    play_sound_once(&sound_laser);
-                }
-                if is_key_pressed(KeyCode::Escape) {
-                    game_state = GameState::Paused;
-                }
-
-                // Clamp X and Y to be within the screen
-                circle.x = circle.x.min(screen_width()).max(0.0);
-                circle.y = circle.y.min(screen_height()).max(0.0);
-
-                // Generate a new square
-                if rand::gen_range(0, 99) >= 95 {
-                    let size = rand::gen_range(16.0, 64.0);
-                    squares.push(Shape {
-                        size,
-                        speed: rand::gen_range(50.0, 150.0),
-                        x: rand::gen_range(size / 2.0, screen_width() - size / 2.0),
-                        y: -size,
-                        collided: false,
-                    });
-                }
+        if game_state != GameState::EnterName && is_key_pressed(KeyCode::M) {
+            ctx.toggle_audio();
+        }
+        if game_state != GameState::EnterName && is_key_pressed(KeyCode::F) {
+            ctx.settings.show_fps = !ctx.settings.show_fps;
+            ctx.settings.save().ok();
+        }
+        if is_key_pressed(KeyCode::F11) {
+            ctx.toggle_fullscreen();
+        }
+        if is_key_pressed(KeyCode::F1) {
+            ctx.debug = !ctx.debug;
+        }
 
-                // Movement
-                for square in &mut squares {
-                    square.y += square.speed * delta_time;
-                }
-                for bullet in &mut bullets {
-                    bullet.y -= bullet.speed * delta_time;
+        let next_state = if transition.is_active() {
+            transition.update()
+        } else {
+            match screen.update(&mut ctx) {
+                Some(next_state) if game_state.fades_to(next_state) => {
+                    transition.start(next_state);
+                    None
                 }
+                next_state => next_state,
+            }
+        };
+        if let Some(next_state) = next_state {
+            game_state = next_state;
+            screen = game_state.screen();
+        }
+        screen.draw(&mut ctx);
+        if ctx.debug
+            && matches!(
+                game_state,
+                GameState::Countdown | GameState::Playing | GameState::Paused | GameState::Dying
+            )
+        {
+            draw_debug_overlay(&ctx);
+        }
+        transition.draw();
+        ctx.update_toasts(get_frame_time());
+        draw_toasts(&ctx);
+        fps_counter.update();
+        if ctx.settings.show_fps {
+            fps_counter.draw(ctx.palette());
+        }
+        ctx.post_process.end();
 
-                ship_sprite.update();
-                bullet_sprite.update();
-                enemy_small_sprite.update();
-
-                // Remove shapes outside of screen
-                squares.retain(|square| square.y < screen_height() + square.size);
-                bullets.retain(|bullet| bullet.y > 0.0 - bullet.size / 2.0);
-
-                // Remove collided shapes
-                squares.retain(|square| !square.collided);
-                bullets.retain(|bullet| !bullet.collided);
-
-                // Remove old explosions
-                explosions.retain(|(explosion, _)| explosion.config.emitting);
-
-                // Check for collisions
-                if squares.iter().any(|square| circle.collides_with(square)) {
-                    if score == high_score {
-                        fs::write("highscore.dat", high_score.to_string()).ok();
-                    }
-                    play_sound_once(&sound_hit);
-                    game_state = GameState::GameOver;
-                }
-                for square in squares.iter_mut() {
-                    for bullet in bullets.iter_mut() {
-                        if bullet.collides_with(square) {
-                            bullet.collided = true;
-                            square.collided = true;
-                            score += square.size.round() as u32;
-                            high_score = high_score.max(score);
-                            explosions.push((
-                                Emitter::new(EmitterConfig {
-                                    amount: square.size.round() as u32 * 4,
-                                    texture: Some(explosions_texture.clone()),
-                                    ..particle_explosion()
-                                }),
-                                vec2(square.x, square.y),
-                            ));
-                            play_sound_once(&sound_explosion);
-                        }
-                    }
+        if is_key_pressed(KeyCode::F12) {
+            let text = match save_screenshot() {
+                Ok(_) => Text::ScreenshotSaved,
+                Err(err) => {
+                    eprintln!("Warning: couldn't save screenshot ({})", err);
+                    Text::ScreenshotFailed
                 }
+            };
+            ctx.push_toast(ctx.tr(text).to_string(), ctx.palette().text);
+        }
 
-                // Draw everything
-                let bullet_frame = bullet_sprite.frame();
-                for bullet in &bullets {
-                    draw_texture_ex(
-                        &bullet_texture,
-                        bullet.x - bullet.size / 2.0,
-                        bullet.y - bullet.size / 2.0,
-                        WHITE,
-                        DrawTextureParams {
-                            dest_size: Some(vec2(bullet.size, bullet.size)),
-                            source: Some(bullet_frame.source_rect),
-                            ..Default::default()
-                        },
-                    );
-                }
-                let ship_frame = ship_sprite.frame();
-                draw_texture_ex(
-                    &ship_texture,
-                    circle.x - ship_frame.dest_size.x,
-                    circle.y - ship_frame.dest_size.y,
-                    WHITE,
-                    DrawTextureParams {
-                        dest_size: Some(ship_frame.dest_size * 2.0),
-                        source: Some(ship_frame.source_rect),
-                        ..Default::default()
-                    },
-                );
-                let enemy_frame = enemy_small_sprite.frame();
-                for square in &squares {
-                    draw_texture_ex(
-                        &enemy_small_texture,
-                        square.x - square.size / 2.0,
-                        square.y - square.size / 2.0,
-                        WHITE,
-                        DrawTextureParams {
-                            dest_size: Some(vec2(square.size, square.size)),
-                            source: Some(enemy_frame.source_rect),
-                            ..Default::default()
-                        },
-                    );
-                }
-                for (explosion, coords) in explosions.iter_mut() {
-                    explosion.draw(*coords);
-                }
-                draw_text(
-                    format!("Poäng: {}", score).as_str(),
-                    10.0,
-                    35.0,
-                    25.0,
-                    WHITE,
-                );
-                let highscore_text = format!("High score: {}", high_score);
-                let text_dimensions = measure_text(highscore_text.as_str(), None, 25, 1.0);
-                draw_text(
-                    highscore_text.as_str(),
-                    screen_width() - text_dimensions.width - 10.0,
-                    35.0,
-                    25.0,
-                    WHITE,
-                );
+        match ctx.settings.frame_cap.fps() {
+            // Nudge the sleep toward whatever brings the whole frame, drawing
+            // and waiting for the display included, to the target length
+            Some(fps) => {
+                let target = 1.0 / fps as f32;
+                frame_sleep = (frame_sleep + target - get_frame_time()).clamp(0.0, target);
             }
-            GameState::Paused => {
-                if is_key_pressed(KeyCode::Escape) {
-                    game_state = GameState::Playing;
-                }
-                let text = "Pausad";
-                let text_dimensions = measure_text(text, None, 50, 1.0);
-                draw_text(
-                    text,
-                    screen_width() / 2.0 - text_dimensions.width / 2.0,
-                    screen_height() / 2.0,
-                    50.0,
-                    WHITE,
-                );
+            None => frame_sleep = 0.0,
+        }
+        // Browsers pace the frames themselves, and can't sleep
+        #[cfg(not(target_arch = "wasm32"))]
+        if frame_sleep > 0.0 {
+            let deadline = get_time() + frame_sleep as f64;
+            if frame_sleep > SPIN_WAIT_TIME {
+                std::thread::sleep(std::time::Duration::from_secs_f32(
+                    frame_sleep - SPIN_WAIT_TIME,
+                ));
             }
-            GameState::GameOver => {
-                if is_key_pressed(KeyCode::Space) {
-                    game_state = GameState::MainMenu;
-                }
-                let text = "Game Over!";
-                let text_dimensions = measure_text(text, None, 50, 1.0);
-                draw_text(
-                    text,
-                    screen_width() / 2.0 - text_dimensions.width / 2.0,
-                    screen_height() / 2.0,
-                    50.0,
-                    RED,
-                );
+            while get_time() < deadline {
+                std::hint::spin_loop();
             }
         }
 
         next_frame().await
     }
-}
\ No newline at end of file
+}
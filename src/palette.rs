@@ -0,0 +1,153 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::language::Text;
+
+/// The color themes to pick from on the settings screen.
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Classic,
+    Neon,
+    Monochrome,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Classic, Theme::Neon, Theme::Monochrome];
+
+    pub fn label(self) -> Text {
+        match self {
+            Theme::Classic => Text::Classic,
+            Theme::Neon => Text::Neon,
+            Theme::Monochrome => Text::Monochrome,
+        }
+    }
+
+    pub fn palette(self) -> &'static Palette {
+        match self {
+            Theme::Classic => &CLASSIC,
+            Theme::Neon => &NEON,
+            Theme::Monochrome => &MONOCHROME,
+        }
+    }
+}
+
+/// The colors everything in the game is drawn with. Sprites keep their own
+/// colors and are only tinted by the enemy colors.
+pub struct Palette {
+    pub background: Color,
+    /// Menu and HUD text, and button outlines
+    pub text: Color,
+    /// Hints and other less important text
+    pub dim_text: Color,
+    /// The least important text, like the stage and frame rate
+    pub faint_text: Color,
+    /// Selected menu items and hovered buttons
+    pub highlight: Color,
+    /// New records and names on the leaderboard
+    pub reward: Color,
+    /// Game over, the boss health bar and other bad news
+    pub danger: Color,
+    /// The ship when its sprite is missing, and the lives in the HUD
+    pub player: Color,
+    /// Glow around the ship while a shot is charging
+    pub charge: Color,
+    pub combo: Color,
+    pub grunt: Color,
+    pub fast: Color,
+    pub tank: Color,
+    pub friendly: Color,
+    pub splitter: Color,
+    pub zig_zag: Color,
+    pub homing: Color,
+    pub bullet_trail: Color,
+    pub enemy_bullet: Color,
+    pub coin: Color,
+    pub rapid_fire: Color,
+    pub spread: Color,
+    pub magnet: Color,
+    /// The time slow pickup, and the tint over the screen while it lasts
+    pub time_slow: Color,
+}
+
+const CLASSIC: Palette = Palette {
+    background: BLACK,
+    text: WHITE,
+    dim_text: LIGHTGRAY,
+    faint_text: GRAY,
+    highlight: YELLOW,
+    reward: GOLD,
+    danger: RED,
+    player: RED,
+    charge: Color::new(1.0, 0.9, 0.3, 1.0),
+    combo: ORANGE,
+    grunt: WHITE,
+    fast: SKYBLUE,
+    tank: LIGHTGRAY,
+    friendly: GREEN,
+    splitter: VIOLET,
+    zig_zag: PINK,
+    homing: ORANGE,
+    bullet_trail: ORANGE,
+    enemy_bullet: SKYBLUE,
+    coin: GOLD,
+    rapid_fire: YELLOW,
+    spread: LIME,
+    magnet: GOLD,
+    time_slow: Color::new(0.5, 0.7, 1.0, 1.0),
+};
+
+const NEON: Palette = Palette {
+    background: Color::new(0.03, 0.0, 0.08, 1.0),
+    text: WHITE,
+    dim_text: Color::new(0.6, 0.8, 1.0, 1.0),
+    faint_text: Color::new(0.4, 0.45, 0.75, 1.0),
+    highlight: Color::new(0.0, 1.0, 1.0, 1.0),
+    reward: Color::new(1.0, 0.9, 0.2, 1.0),
+    danger: Color::new(1.0, 0.1, 0.5, 1.0),
+    player: Color::new(1.0, 0.0, 0.8, 1.0),
+    charge: Color::new(0.4, 1.0, 1.0, 1.0),
+    combo: Color::new(1.0, 0.5, 0.0, 1.0),
+    grunt: Color::new(0.9, 0.5, 1.0, 1.0),
+    fast: Color::new(0.0, 1.0, 1.0, 1.0),
+    tank: Color::new(0.5, 0.5, 1.0, 1.0),
+    friendly: Color::new(0.2, 1.0, 0.3, 1.0),
+    splitter: Color::new(0.7, 0.3, 1.0, 1.0),
+    zig_zag: Color::new(1.0, 0.3, 0.8, 1.0),
+    homing: Color::new(1.0, 0.6, 0.1, 1.0),
+    bullet_trail: Color::new(0.0, 1.0, 1.0, 1.0),
+    enemy_bullet: Color::new(1.0, 0.2, 0.6, 1.0),
+    coin: Color::new(1.0, 0.9, 0.2, 1.0),
+    rapid_fire: Color::new(1.0, 1.0, 0.2, 1.0),
+    spread: Color::new(0.3, 1.0, 0.3, 1.0),
+    magnet: Color::new(1.0, 0.7, 0.1, 1.0),
+    time_slow: Color::new(0.3, 0.5, 1.0, 1.0),
+};
+
+/// Shades of gray, told apart by brightness alone.
+const MONOCHROME: Palette = Palette {
+    background: BLACK,
+    text: WHITE,
+    dim_text: Color::new(0.75, 0.75, 0.75, 1.0),
+    faint_text: Color::new(0.5, 0.5, 0.5, 1.0),
+    highlight: WHITE,
+    reward: Color::new(0.9, 0.9, 0.9, 1.0),
+    danger: Color::new(0.6, 0.6, 0.6, 1.0),
+    player: WHITE,
+    charge: WHITE,
+    combo: Color::new(0.85, 0.85, 0.85, 1.0),
+    grunt: WHITE,
+    fast: Color::new(0.8, 0.8, 0.8, 1.0),
+    tank: Color::new(0.6, 0.6, 0.6, 1.0),
+    friendly: Color::new(0.4, 0.4, 0.4, 1.0),
+    splitter: Color::new(0.65, 0.65, 0.65, 1.0),
+    zig_zag: Color::new(0.9, 0.9, 0.9, 1.0),
+    homing: Color::new(0.7, 0.7, 0.7, 1.0),
+    bullet_trail: Color::new(0.8, 0.8, 0.8, 1.0),
+    enemy_bullet: Color::new(0.6, 0.6, 0.6, 1.0),
+    coin: Color::new(0.9, 0.9, 0.9, 1.0),
+    rapid_fire: WHITE,
+    spread: Color::new(0.75, 0.75, 0.75, 1.0),
+    magnet: Color::new(0.55, 0.55, 0.55, 1.0),
+    time_slow: Color::new(0.85, 0.85, 0.85, 1.0),
+};
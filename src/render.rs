@@ -0,0 +1,1539 @@
+use macroquad::experimental::animation::{AnimatedSprite, Animation};
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles, AtlasConfig, Emitter, EmitterConfig};
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use crate::entities::{Boss, Collider, Enemy, EnemyKind, Shape};
+use crate::language::Text;
+use crate::palette::Palette;
+use crate::settings::GameMode;
+use crate::state::{ramp_stage, GameContext, HEARTBEAT_INTERVAL, TIME_SLOW_SCALE};
+
+/// Most explosions that can be running at the same time
+const MAX_EXPLOSIONS: usize = 32;
+/// Width and height of a menu button
+const BUTTON_SIZE: Vec2 = vec2(160.0, 40.0);
+/// Space between buttons laid out next to each other
+const BUTTON_GAP: f32 = 20.0;
+/// Number of fading copies drawn behind a bullet
+const TRAIL_LENGTH: usize = 4;
+/// Seconds of travel between each copy in a bullet's trail
+const TRAIL_SPACING: f32 = 0.012;
+/// Width and height of the bar showing how long the combo has left
+const COMBO_BAR_SIZE: Vec2 = vec2(100.0, 5.0);
+/// Combo at which the combo bar is drawn at full strength
+const COMBO_BAR_FULL: u32 = 8;
+/// Window height the UI is laid out for. Text and the spacing around it
+/// grow and shrink from there along with the window.
+const UI_REFERENCE_HEIGHT: f32 = 600.0;
+/// How far the shadow behind HUD text is offset, in pixels
+const TEXT_SHADOW_OFFSET: f32 = 2.0;
+/// Opacity of the shadow behind HUD text
+const TEXT_SHADOW_ALPHA: f32 = 0.6;
+/// Font size of the watermark over practice runs
+const PRACTICE_WATERMARK_SIZE: f32 = 120.0;
+/// Opacity of the watermark over practice runs
+const PRACTICE_WATERMARK_ALPHA: f32 = 0.12;
+/// How quickly the boss warning flashes
+const BOSS_WARNING_PULSE: f32 = 6.0;
+/// How far the low health vignette reaches in from the screen edges
+const VIGNETTE_WIDTH: f32 = 80.0;
+/// Number of bands the low health vignette fades out over
+const VIGNETTE_STEPS: u32 = 8;
+/// Opacity of the tint over the screen at the height of a time slow
+const TIME_SLOW_TINT: f32 = 0.15;
+/// Seconds left in a time attack run when the clock turns red
+const TIME_ATTACK_WARNING: f32 = 10.0;
+/// Most toasts shown at once; the oldest is dropped to make room
+pub const MAX_TOASTS: usize = 4;
+/// Seconds a toast stays up
+const TOAST_LIFETIME: f32 = 2.5;
+/// Seconds a toast takes to slide in, and back out at the end of its lifetime
+const TOAST_SLIDE_TIME: f32 = 0.3;
+/// Seconds a toast takes to fade out at the end of its lifetime
+const TOAST_FADE_TIME: f32 = 0.5;
+/// How far the ship leans while steering sideways, in radians
+const SHIP_TILT: f32 = 0.15;
+
+const FRAGMENT_SHADER: &str = include_str!("starfield-shader.glsl");
+const CRT_FRAGMENT_SHADER: &str = include_str!("crt-shader.glsl");
+
+const VERTEX_SHADER: &str = "#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+varying float iTime;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+uniform vec4 _Time;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    iTime = _Time.x;
+}
+";
+
+/// Number of stars and scroll speed in pixels per second of each parallax
+/// layer, from the farthest to the nearest
+const STAR_LAYERS: [(usize, f32); 3] = [(60, 20.0), (35, 45.0), (15, 90.0)];
+/// How much faster the star layers drift sideways than down at full warp
+const STAR_SIDEWAYS_SCALE: f32 = 2.0;
+/// How much faster and longer the star layers get at full warp intensity
+const STAR_WARP_STRETCH: f32 = 2.0;
+
+/// The scrolling star background, drawn through a shader before everything else.
+pub struct Starfield {
+    render_target: RenderTarget,
+    material: Material,
+    layers: Vec<StarLayer>,
+    /// Screen size the stars were last placed for
+    screen_size: Vec2,
+}
+
+/// Plain stars drifting down in front of the shader background. Faster
+/// layers are drawn bigger and brighter so they look closer.
+struct StarLayer {
+    stars: Vec<Vec2>,
+    speed: f32,
+}
+
+impl StarLayer {
+    fn new(count: usize, speed: f32) -> Self {
+        let stars = (0..count)
+            .map(|_| {
+                vec2(
+                    rand::gen_range(0.0, screen_width()),
+                    rand::gen_range(0.0, screen_height()),
+                )
+            })
+            .collect();
+        Self { stars, speed }
+    }
+
+    /// Scrolls the stars down, and sideways against the starfield warp,
+    /// wrapping the ones that leave the screen around to the other edge.
+    fn update(&mut self, dt: f32, direction_modifier: f32, warp_intensity: f32) {
+        let velocity = vec2(-direction_modifier * STAR_SIDEWAYS_SCALE, 1.0)
+            * self.speed
+            * (1.0 + warp_intensity * STAR_WARP_STRETCH);
+        for star in &mut self.stars {
+            *star += velocity * dt;
+            star.x = star.x.rem_euclid(screen_width());
+            star.y = star.y.rem_euclid(screen_height());
+        }
+    }
+
+    fn draw(&self, warp_intensity: f32) {
+        let size = self.speed / 45.0;
+        let length = size * (1.0 + warp_intensity * STAR_WARP_STRETCH);
+        let color = Color::new(1.0, 1.0, 1.0, (self.speed / 90.0).min(1.0) * 0.8);
+        for star in &self.stars {
+            draw_rectangle(star.x, star.y, size, length, color);
+        }
+    }
+}
+
+impl Starfield {
+    pub fn new() -> Self {
+        let render_target = render_target(320, 150);
+        render_target.texture.set_filter(FilterMode::Nearest);
+        let material = load_material(
+            ShaderSource::Glsl {
+                vertex: VERTEX_SHADER,
+                fragment: FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                uniforms: vec![
+                    ("iResolution".to_owned(), UniformType::Float2),
+                    ("direction_modifier".to_owned(), UniformType::Float1),
+                    ("warp_intensity".to_owned(), UniformType::Float1),
+                ],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let layers = STAR_LAYERS
+            .iter()
+            .map(|&(count, speed)| StarLayer::new(count, speed))
+            .collect();
+        Self {
+            render_target,
+            material,
+            layers,
+            screen_size: vec2(screen_width(), screen_height()),
+        }
+    }
+
+    /// Spreads the stars out over the new screen size when the window has
+    /// been resized or switched to or from fullscreen.
+    fn handle_resize(&mut self) {
+        let size = vec2(screen_width(), screen_height());
+        if size == self.screen_size || size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+        let scale = size / self.screen_size;
+        self.screen_size = size;
+        for layer in &mut self.layers {
+            for star in &mut layer.stars {
+                *star *= scale;
+            }
+        }
+    }
+
+    /// Draws the stars leaning with `direction_modifier`, scaled by
+    /// `scroll_scale` so the lean can be toned down or turned around.
+    pub fn draw(&mut self, direction_modifier: f32, warp_intensity: f32, scroll_scale: f32) {
+        self.handle_resize();
+        let direction_modifier = direction_modifier * scroll_scale;
+        // The shader works in physical pixels, which on a high-DPI display
+        // outnumber the logical ones everything else is laid out in
+        let dpi_scale = screen_dpi_scale();
+        self.material.set_uniform(
+            "iResolution",
+            (screen_width() * dpi_scale, screen_height() * dpi_scale),
+        );
+        self.material
+            .set_uniform("direction_modifier", direction_modifier);
+        self.material.set_uniform("warp_intensity", warp_intensity);
+        gl_use_material(&self.material);
+        draw_texture_ex(
+            &self.render_target.texture,
+            0.,
+            0.,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(screen_width(), screen_height())),
+                ..Default::default()
+            },
+        );
+        gl_use_default_material();
+
+        let dt = get_frame_time();
+        for layer in &mut self.layers {
+            layer.update(dt, direction_modifier, warp_intensity);
+            layer.draw(warp_intensity);
+        }
+    }
+}
+
+const CRT_VERTEX_SHADER: &str = "#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+varying vec2 uv;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    uv = texcoord;
+}
+";
+
+/// The optional CRT look. While it is on, each frame is drawn to a texture
+/// which is then drawn to the screen through the CRT shader. While it is off
+/// the frame goes straight to the screen as usual.
+pub struct PostProcess {
+    material: Material,
+    /// Texture the frame is drawn to, only kept while the effect is on
+    target: Option<RenderTarget>,
+}
+
+impl PostProcess {
+    pub fn new() -> Self {
+        let material = load_material(
+            ShaderSource::Glsl {
+                vertex: CRT_VERTEX_SHADER,
+                fragment: CRT_FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                uniforms: vec![("iResolution".to_owned(), UniformType::Float2)],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        Self {
+            material,
+            target: None,
+        }
+    }
+
+    /// Starts a new frame, sending everything drawn after this to the
+    /// texture if the effect is `enabled`.
+    pub fn begin(&mut self, enabled: bool) {
+        if !enabled {
+            self.target = None;
+            return;
+        }
+        // At the display's full resolution, so high-DPI screens stay sharp
+        let dpi_scale = screen_dpi_scale();
+        let (width, height) = (
+            (screen_width() * dpi_scale) as u32,
+            (screen_height() * dpi_scale) as u32,
+        );
+        let outdated = self.target.as_ref().is_none_or(|target| {
+            target.texture.width() as u32 != width || target.texture.height() as u32 != height
+        });
+        if outdated {
+            self.target = Some(render_target(width, height));
+        }
+        self.set_camera(Vec2::ZERO);
+    }
+
+    /// Points drawing at the frame, moved by `offset` pixels.
+    pub fn set_camera(&self, offset: Vec2) {
+        if self.target.is_none() && offset == Vec2::ZERO {
+            set_default_camera();
+            return;
+        }
+        let mut camera = Camera2D::from_display_rect(Rect::new(
+            offset.x,
+            offset.y,
+            screen_width(),
+            screen_height(),
+        ));
+        camera.render_target = self.target.clone();
+        set_camera(&camera);
+    }
+
+    /// Draws the finished frame to the screen through the CRT shader. Does
+    /// nothing while the effect is off.
+    pub fn end(&self) {
+        let Some(target) = &self.target else {
+            return;
+        };
+        set_default_camera();
+        self.material
+            .set_uniform("iResolution", (screen_width(), screen_height()));
+        gl_use_material(&self.material);
+        draw_texture_ex(
+            &target.texture,
+            0.,
+            0.,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(screen_width(), screen_height())),
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+        gl_use_default_material();
+    }
+}
+
+const FONT_FILE: &str = "atari_games.ttf";
+
+thread_local! {
+    /// The font all text is drawn in, or `None` for macroquad's built-in one
+    /// if the font file couldn't be loaded
+    static FONT: RefCell<Option<GameFont>> = const { RefCell::new(None) };
+}
+
+struct GameFont {
+    font: Font,
+    /// The same font parsed again to look up which characters it has
+    glyphs: fontdue::Font,
+}
+
+impl GameFont {
+    /// Whether the font has a glyph for every character of `text`. It
+    /// lacks å, ä and ö, so much of the Swedish text isn't covered.
+    fn covers(&self, text: &str) -> bool {
+        text.chars().all(|c| self.glyphs.lookup_glyph_index(c) != 0)
+    }
+}
+
+/// Loads the font used for all text, falling back to the built-in font if
+/// the file is missing.
+async fn load_font() {
+    let loaded = match load_file(FONT_FILE).await {
+        Ok(bytes) => load_ttf_font_from_bytes(&bytes)
+            .map_err(|err| err.to_string())
+            .and_then(|font| {
+                let glyphs =
+                    fontdue::Font::from_bytes(bytes.as_slice(), fontdue::FontSettings::default())?;
+                Ok(GameFont { font, glyphs })
+            }),
+        Err(err) => Err(err.to_string()),
+    };
+    match loaded {
+        Ok(mut font) => {
+            font.font.set_filter(FilterMode::Nearest);
+            FONT.with_borrow_mut(|slot| *slot = Some(font));
+        }
+        Err(err) => eprintln!(
+            "Warning: couldn't load {} ({}), using the default font",
+            FONT_FILE, err
+        ),
+    }
+}
+
+/// The font to draw `text` in: the game's font if it can render all of it,
+/// and the built-in one otherwise.
+fn font_for<'a>(font: &'a Option<GameFont>, text: &str) -> Option<&'a Font> {
+    font.as_ref()
+        .filter(|font| font.covers(text))
+        .map(|font| &font.font)
+}
+
+/// How much bigger than at the reference height the UI is drawn, so it
+/// takes up the same share of the window at any resolution.
+pub fn ui_scale() -> f32 {
+    screen_height() / UI_REFERENCE_HEIGHT
+}
+
+/// Draws text in the game's font, with `font_size` scaled by `ui_scale`.
+/// Every piece of UI text goes through this rather than macroquad's
+/// `draw_text`, so the font and scale are the same everywhere.
+pub fn draw_ui_text(text: &str, x: f32, y: f32, font_size: f32, color: Color) -> TextDimensions {
+    draw_font_text(text, x, y, font_size * ui_scale(), color)
+}
+
+/// Measures text as `draw_ui_text` would draw it.
+pub fn measure_ui_text(text: &str, font_size: u16) -> TextDimensions {
+    measure_font_text(text, (font_size as f32 * ui_scale()) as u16)
+}
+
+/// Draws text in the game's font at its exact pixel size, for text on the
+/// playing field that has to fit the sprites it labels.
+fn draw_font_text(text: &str, x: f32, y: f32, font_size: f32, color: Color) -> TextDimensions {
+    FONT.with_borrow(|font| {
+        draw_text_ex(
+            text,
+            x,
+            y,
+            TextParams {
+                font: font_for(font, text),
+                font_size: font_size as u16,
+                color,
+                ..Default::default()
+            },
+        )
+    })
+}
+
+/// Measures text as `draw_font_text` would draw it.
+fn measure_font_text(text: &str, font_size: u16) -> TextDimensions {
+    FONT.with_borrow(|font| measure_text(text, font_for(font, text), font_size, 1.0))
+}
+
+pub struct Assets {
+    /// Missing if `ship.png` couldn't be loaded, in which case the ship is
+    /// drawn as a plain circle
+    pub ship_texture: Option<Texture2D>,
+    pub bullet_texture: Texture2D,
+    pub explosions_texture: Texture2D,
+    pub enemy_small_texture: Texture2D,
+    pub enemy_big_texture: Texture2D,
+}
+
+impl Assets {
+    pub async fn load() -> Self {
+        set_pc_assets_folder("assets");
+        let ship_texture = match load_texture("ship.png").await {
+            Ok(texture) => {
+                texture.set_filter(FilterMode::Nearest);
+                Some(texture)
+            }
+            Err(err) => {
+                eprintln!(
+                    "Warning: couldn't load ship.png ({}), drawing the ship as a circle",
+                    err
+                );
+                None
+            }
+        };
+        let bullet_texture: Texture2D = load_texture("laser-bolts.png")
+            .await
+            .expect("Couldn't load file");
+        bullet_texture.set_filter(FilterMode::Nearest);
+        let explosions_texture: Texture2D = load_texture("explosion.png")
+            .await
+            .expect("Couldn't lead file");
+        explosions_texture.set_filter(FilterMode::Nearest);
+
+        let enemy_small_texture: Texture2D = load_texture("enemy-small.png")
+            .await
+            .expect("Couldn't load file");
+        enemy_small_texture.set_filter(FilterMode::Nearest);
+        let enemy_big_texture: Texture2D = load_texture("enemy-big.png")
+            .await
+            .expect("Couldn't load file");
+        enemy_big_texture.set_filter(FilterMode::Nearest);
+        build_textures_atlas();
+        load_font().await;
+
+        Self {
+            ship_texture,
+            bullet_texture,
+            explosions_texture,
+            enemy_small_texture,
+            enemy_big_texture,
+        }
+    }
+}
+
+pub struct Sprites {
+    pub ship: AnimatedSprite,
+    pub bullet: AnimatedSprite,
+    pub enemy_small: AnimatedSprite,
+    pub boss: AnimatedSprite,
+}
+
+impl Sprites {
+    pub fn new() -> Self {
+        let enemy_small = AnimatedSprite::new(
+            17,
+            16,
+            &[Animation {
+                name: "enemy_small".to_string(),
+                row: 0,
+                frames: 2,
+                fps: 12,
+            }],
+            true,
+        );
+        let boss = AnimatedSprite::new(
+            32,
+            32,
+            &[Animation {
+                name: "boss".to_string(),
+                row: 0,
+                frames: 2,
+                fps: 12,
+            }],
+            true,
+        );
+        let ship = AnimatedSprite::new(
+            16,
+            24,
+            &[
+                Animation {
+                    name: "idle".to_string(),
+                    row: 0,
+                    frames: 2,
+                    fps: 12,
+                },
+                Animation {
+                    name: "left".to_string(),
+                    row: 2,
+                    frames: 2,
+                    fps: 12,
+                },
+                Animation {
+                    name: "left2".to_string(),
+                    row: 2,
+                    frames: 2,
+                    fps: 12,
+                },
+                Animation {
+                    name: "right".to_string(),
+                    row: 4,
+                    frames: 2,
+                    fps: 12,
+                },
+                Animation {
+                    name: "right2".to_string(),
+                    row: 4,
+                    frames: 2,
+                    fps: 12,
+                },
+            ],
+            true,
+        );
+        let mut bullet = AnimatedSprite::new(
+            16,
+            16,
+            &[
+                Animation {
+                    name: "bullet".to_string(),
+                    row: 0,
+                    frames: 2,
+                    fps: 12,
+                },
+                Animation {
+                    name: "bolt".to_string(),
+                    row: 1,
+                    frames: 2,
+                    fps: 12,
+                },
+            ],
+            true,
+        );
+        bullet.set_animation(1);
+        Self {
+            ship,
+            bullet,
+            enemy_small,
+            boss,
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.ship.update();
+        self.bullet.update();
+        self.enemy_small.update();
+        self.boss.update();
+    }
+}
+
+/// The particles are tinted with `color`, and with reduced motion they fly
+/// out slower and there are fewer of them.
+pub fn particle_explosion(color: Color, reduce_motion: bool) -> particles::EmitterConfig {
+    particles::EmitterConfig {
+        local_coords: false,
+        one_shot: true,
+        emitting: true,
+        lifetime: 0.6,
+        lifetime_randomness: 0.3,
+        explosiveness: 0.65,
+        initial_direction_spread: 2.0 * std::f32::consts::PI,
+        initial_velocity: if reduce_motion { 150.0 } else { 400.0 },
+        initial_velocity_randomness: 0.8,
+        size: 16.0,
+        size_randomness: 0.3,
+        atlas: Some(AtlasConfig::new(5, 1, 0..)),
+        colors_curve: particles::ColorCurve {
+            start: color,
+            mid: color,
+            end: color,
+        },
+        ..Default::default()
+    }
+}
+
+/// A small burst of yellow and white sparks from the nose of the ship when
+/// it fires. With reduced motion there are fewer, slower sparks.
+pub fn muzzle_flash(reduce_motion: bool) -> particles::EmitterConfig {
+    particles::EmitterConfig {
+        amount: if reduce_motion { 3 } else { 8 },
+        lifetime: 0.15,
+        explosiveness: 1.0,
+        initial_direction: vec2(0.0, -1.0),
+        initial_direction_spread: 1.2,
+        initial_velocity: if reduce_motion { 60.0 } else { 150.0 },
+        size: 4.0,
+        atlas: None,
+        colors_curve: particles::ColorCurve {
+            start: WHITE,
+            mid: YELLOW,
+            end: Color::new(1.0, 0.6, 0.0, 0.0),
+        },
+        ..particle_explosion(WHITE, reduce_motion)
+    }
+}
+
+/// A big white burst cooling to blue where the ship was destroyed, lasting
+/// long enough to be seen through the slow-motion death. With reduced motion
+/// there are fewer, slower particles.
+pub fn player_explosion(reduce_motion: bool) -> particles::EmitterConfig {
+    particles::EmitterConfig {
+        amount: if reduce_motion { 80 } else { 400 },
+        lifetime: 1.2,
+        size: 28.0,
+        colors_curve: particles::ColorCurve {
+            start: WHITE,
+            mid: Color::new(0.5, 0.8, 1.0, 1.0),
+            end: Color::new(0.1, 0.3, 1.0, 0.0),
+        },
+        ..particle_explosion(WHITE, reduce_motion)
+    }
+}
+
+/// A short message in the corner of the screen, like a power-up being
+/// picked up or an achievement being unlocked.
+pub struct Toast {
+    pub text: String,
+    pub color: Color,
+    /// Seconds since it appeared
+    pub age: f32,
+}
+
+impl Toast {
+    pub fn new(text: String, color: Color) -> Self {
+        Self {
+            text,
+            color,
+            age: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.age += dt;
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.age >= TOAST_LIFETIME
+    }
+}
+
+/// The running explosions. A bomb or a boss can set off dozens at once, so
+/// the oldest explosion is dropped once `MAX_EXPLOSIONS` are running to keep
+/// the frame rate up.
+pub struct Explosions {
+    emitters: VecDeque<(Emitter, Vec2)>,
+}
+
+impl Explosions {
+    pub fn new() -> Self {
+        Self {
+            emitters: VecDeque::with_capacity(MAX_EXPLOSIONS),
+        }
+    }
+
+    pub fn push(&mut self, explosion: (Emitter, Vec2)) {
+        if self.emitters.len() == MAX_EXPLOSIONS {
+            self.emitters.pop_front();
+        }
+        self.emitters.push_back(explosion);
+    }
+
+    pub fn extend(&mut self, explosions: impl IntoIterator<Item = (Emitter, Vec2)>) {
+        for explosion in explosions {
+            self.push(explosion);
+        }
+    }
+
+    /// Removes the explosions that have finished.
+    pub fn remove_finished(&mut self) {
+        self.emitters
+            .retain(|(explosion, _)| explosion.config.emitting);
+    }
+
+    pub fn clear(&mut self) {
+        self.emitters.clear();
+    }
+}
+
+/// Creates an explosion sized and tinted to match the destroyed enemy, or a
+/// dull gray one for a friendly.
+pub fn enemy_explosion(
+    enemy: &Enemy,
+    texture: &Texture2D,
+    palette: &Palette,
+    reduce_motion: bool,
+) -> (Emitter, Vec2) {
+    let per_size = if reduce_motion { 1 } else { 4 };
+    (
+        Emitter::new(EmitterConfig {
+            amount: enemy.size.round() as u32 * per_size,
+            texture: Some(texture.clone()),
+            ..particle_explosion(
+                if enemy.kind == EnemyKind::Friendly {
+                    DARKGRAY
+                } else {
+                    enemy.color(palette)
+                },
+                reduce_motion,
+            )
+        }),
+        vec2(enemy.x, enemy.y),
+    )
+}
+
+/// Creates a burst of large explosions spread over the destroyed boss.
+pub fn boss_explosion(
+    boss: &Boss,
+    texture: &Texture2D,
+    reduce_motion: bool,
+) -> Vec<(Emitter, Vec2)> {
+    (0..5)
+        .map(|_| {
+            let offset = vec2(rand::gen_range(-0.5, 0.5), rand::gen_range(-0.5, 0.5)) * boss.size;
+            (
+                Emitter::new(EmitterConfig {
+                    amount: if reduce_motion { 50 } else { 200 },
+                    size: 32.0,
+                    texture: Some(texture.clone()),
+                    ..particle_explosion(WHITE, reduce_motion)
+                }),
+                vec2(boss.x, boss.y) + offset,
+            )
+        })
+        .collect()
+}
+
+/// A frame rate readout that is smoothed over several frames so it doesn't
+/// flicker.
+pub struct FpsCounter {
+    fps: f32,
+}
+
+impl FpsCounter {
+    pub fn new() -> Self {
+        Self { fps: 60.0 }
+    }
+
+    pub fn update(&mut self) {
+        let frame_time = get_frame_time();
+        if frame_time > 0.0 {
+            self.fps += (1.0 / frame_time - self.fps) * 0.1;
+        }
+    }
+
+    pub fn draw(&self, palette: &Palette) {
+        let text = format!("FPS: {}", self.fps.round());
+        let text_dimensions = measure_ui_text(text.as_str(), 20);
+        draw_text_shadowed(
+            text.as_str(),
+            screen_width() / 2.0 - text_dimensions.width / 2.0,
+            screen_height() - 15.0 * ui_scale(),
+            20.0,
+            palette.faint_text,
+        );
+    }
+}
+
+/// Saves what is on screen to a PNG named after the current time, returning
+/// the file name.
+pub fn save_screenshot() -> std::io::Result<String> {
+    let path = format!("screenshot-{}.png", (miniquad::date::now() * 1000.0) as u64);
+    // Creating the file first turns a missing permission or a full disk into
+    // an error here, since `export_png` panics on failure
+    std::fs::File::create(&path)?;
+    get_screen_data().export_png(&path);
+    Ok(path)
+}
+
+/// Slides the toasts in at the top right of the screen, newest at the top,
+/// fading each one out at the end of its lifetime.
+pub fn draw_toasts(ctx: &GameContext) {
+    let palette = ctx.palette();
+    let scale = ui_scale();
+    for (i, toast) in ctx.toasts.iter().rev().enumerate() {
+        let width = measure_ui_text(toast.text.as_str(), 20).width + 20.0 * scale;
+        // How much of the toast is on the screen, sliding in from the edge
+        // when it appears and back out as it expires
+        let shown = if ctx.settings.reduce_motion {
+            1.0
+        } else {
+            (toast.age.min(TOAST_LIFETIME - toast.age) / TOAST_SLIDE_TIME).clamp(0.0, 1.0)
+        };
+        let alpha = ((TOAST_LIFETIME - toast.age) / TOAST_FADE_TIME).clamp(0.0, 1.0);
+        let x = screen_width() - (width + 10.0 * scale) * shown;
+        let y = (100.0 + i as f32 * 36.0) * scale;
+        let background = palette.background;
+        let color = toast.color;
+        draw_rectangle(
+            x,
+            y,
+            width,
+            30.0 * scale,
+            Color::new(background.r, background.g, background.b, 0.8 * alpha),
+        );
+        draw_rectangle_lines(
+            x,
+            y,
+            width,
+            30.0 * scale,
+            2.0,
+            Color::new(color.r, color.g, color.b, alpha),
+        );
+        draw_ui_text(
+            toast.text.as_str(),
+            x + 10.0 * scale,
+            y + 21.0 * scale,
+            20.0,
+            Color::new(color.r, color.g, color.b, alpha),
+        );
+    }
+}
+
+/// Draws text horizontally centered on the screen.
+pub fn draw_centered_text(text: &str, y: f32, font_size: f32, color: Color) {
+    let text_dimensions = measure_ui_text(text, font_size as u16);
+    draw_ui_text(
+        text,
+        screen_width() / 2.0 - text_dimensions.width / 2.0,
+        y,
+        font_size,
+        color,
+    );
+}
+
+/// Draws a row of tab labels centered across the screen, with the selected
+/// one highlighted.
+pub fn draw_tabs(tabs: &[(&str, bool)], y: f32, palette: &Palette) {
+    let gap = 30.0 * ui_scale();
+    let widths: Vec<f32> = tabs
+        .iter()
+        .map(|(label, _)| measure_ui_text(label, 25).width)
+        .collect();
+    let total_width = widths.iter().sum::<f32>() + gap * (tabs.len() as f32 - 1.0);
+    let mut x = screen_width() / 2.0 - total_width / 2.0;
+    for ((label, selected), width) in tabs.iter().zip(widths) {
+        let color = if *selected {
+            palette.highlight
+        } else {
+            palette.faint_text
+        };
+        draw_ui_text(label, x, y, 25.0, color);
+        if *selected {
+            let underline = y + 5.0 * ui_scale();
+            draw_line(x, underline, x + width, underline, 2.0, color);
+        }
+        x += width + gap;
+    }
+}
+
+/// Draws text over a faint dark copy of itself, offset a little down and to
+/// the right, so it stays readable over bright stars and explosions.
+pub fn draw_text_shadowed(
+    text: &str,
+    x: f32,
+    y: f32,
+    font_size: f32,
+    color: Color,
+) -> TextDimensions {
+    let offset = TEXT_SHADOW_OFFSET * ui_scale();
+    draw_ui_text(
+        text,
+        x + offset,
+        y + offset,
+        font_size,
+        Color::new(0.0, 0.0, 0.0, TEXT_SHADOW_ALPHA * color.a),
+    );
+    draw_ui_text(text, x, y, font_size, color)
+}
+
+/// Draws shadowed text horizontally centered on the screen.
+pub fn draw_centered_text_shadowed(text: &str, y: f32, font_size: f32, color: Color) {
+    let text_dimensions = measure_ui_text(text, font_size as u16);
+    draw_text_shadowed(
+        text,
+        screen_width() / 2.0 - text_dimensions.width / 2.0,
+        y,
+        font_size,
+        color,
+    );
+}
+
+/// Darkens everything drawn so far by covering the screen with black at the
+/// given opacity.
+pub fn draw_dim_overlay(alpha: f32) {
+    draw_rectangle(
+        0.0,
+        0.0,
+        screen_width(),
+        screen_height(),
+        Color::new(0.0, 0.0, 0.0, alpha),
+    );
+}
+
+/// Lays out `count` buttons side by side, centered across the screen with
+/// their tops at `y`.
+pub fn button_row(count: usize, y: f32) -> Vec<Rect> {
+    let size = BUTTON_SIZE * ui_scale();
+    let gap = BUTTON_GAP * ui_scale();
+    let width = count as f32 * (size.x + gap) - gap;
+    let left = screen_width() / 2.0 - width / 2.0;
+    (0..count)
+        .map(|i| Rect::new(left + i as f32 * (size.x + gap), y, size.x, size.y))
+        .collect()
+}
+
+/// Lays out `count` buttons under each other, centered across the screen
+/// with the top one at `y`.
+pub fn button_column(count: usize, y: f32) -> Vec<Rect> {
+    let size = BUTTON_SIZE * ui_scale();
+    let gap = BUTTON_GAP * ui_scale();
+    (0..count)
+        .map(|i| {
+            Rect::new(
+                screen_width() / 2.0 - size.x / 2.0,
+                y + i as f32 * (size.y + gap),
+                size.x,
+                size.y,
+            )
+        })
+        .collect()
+}
+
+/// Whether the mouse was clicked on the button this frame.
+pub fn button_clicked(rect: Rect) -> bool {
+    is_mouse_button_pressed(MouseButton::Left) && rect.contains(mouse_position().into())
+}
+
+/// Draws a clickable button, highlighted while the mouse is over it or when
+/// it is `selected` with the keyboard.
+pub fn draw_button(rect: Rect, label: &str, selected: bool, palette: &Palette) {
+    let color = if selected || rect.contains(mouse_position().into()) {
+        palette.highlight
+    } else {
+        palette.text
+    };
+    draw_rectangle(
+        rect.x,
+        rect.y,
+        rect.w,
+        rect.h,
+        Color::new(color.r, color.g, color.b, 0.15),
+    );
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, color);
+    let text_dimensions = measure_ui_text(label, 25);
+    draw_ui_text(
+        label,
+        rect.x + rect.w / 2.0 - text_dimensions.width / 2.0,
+        rect.y + rect.h / 2.0 + text_dimensions.offset_y / 2.0,
+        25.0,
+        color,
+    );
+}
+
+/// Draws everything on the playing field: bullets, pickups, the ship,
+/// enemies and explosions.
+pub fn draw_world(ctx: &GameContext) {
+    let palette = ctx.palette();
+    let bullet_frame = ctx.sprites.bullet.frame();
+    for bullet in ctx.run.bullets.iter() {
+        draw_trail(bullet, palette.bullet_trail);
+        draw_texture_ex(
+            &ctx.assets.bullet_texture,
+            bullet.x - bullet.size / 2.0,
+            bullet.y - bullet.size / 2.0,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(bullet.size, bullet.size)),
+                source: Some(bullet_frame.source_rect),
+                // Point the bolt the way it flies
+                rotation: bullet.vx.atan2(-bullet.vy),
+                ..Default::default()
+            },
+        );
+    }
+    for power_up in &ctx.run.power_ups {
+        draw_circle(
+            power_up.x,
+            power_up.y,
+            power_up.size / 2.0,
+            power_up.kind.color(palette),
+        );
+        let label = power_up.kind.label();
+        let label_dimensions = measure_font_text(label, 20);
+        draw_font_text(
+            label,
+            power_up.x - label_dimensions.width / 2.0,
+            power_up.y + label_dimensions.height / 2.0,
+            20.0,
+            palette.background,
+        );
+    }
+    for coin in &ctx.run.coins {
+        draw_circle(coin.x, coin.y, coin.size / 2.0, palette.coin);
+    }
+    for bullet in &ctx.run.enemy_bullets {
+        draw_trail(bullet, palette.enemy_bullet);
+        draw_circle(bullet.x, bullet.y, bullet.size / 2.0, palette.enemy_bullet);
+    }
+    if ctx.run.circle.has_magnet() {
+        let radius = ctx.run.circle.size * (1.5 + 0.1 * (get_time() * 4.0).sin() as f32);
+        draw_circle_lines(
+            ctx.run.circle.x,
+            ctx.run.circle.y,
+            radius,
+            2.0,
+            Color::new(palette.magnet.r, palette.magnet.g, palette.magnet.b, 0.3),
+        );
+    }
+    if ctx.run.graze_pulse > 0.0 {
+        let rect = ctx.run.circle.graze_rect();
+        draw_circle_lines(
+            ctx.run.circle.x,
+            ctx.run.circle.y,
+            rect.w / 2.0 * (2.0 - ctx.run.graze_pulse),
+            1.5,
+            Color::new(
+                palette.text.r,
+                palette.text.g,
+                palette.text.b,
+                0.5 * ctx.run.graze_pulse,
+            ),
+        );
+    }
+    // Blink the ship while it is invincible after a respawn, and leave it
+    // out once it has been destroyed
+    if ctx.run.circle.lives > 0
+        && (!ctx.run.circle.is_invincible() || (get_time() * 30.0).sin() > 0.0)
+    {
+        draw_player(ctx);
+    }
+    for square in &ctx.run.squares {
+        // Tanks borrow the big ship so they stand out from the smaller kinds
+        let (texture, frame) = match square.kind {
+            EnemyKind::Tank => (&ctx.assets.enemy_big_texture, ctx.sprites.boss.frame()),
+            EnemyKind::Grunt | EnemyKind::Fast | EnemyKind::Friendly | EnemyKind::Splitter => (
+                &ctx.assets.enemy_small_texture,
+                ctx.sprites.enemy_small.frame(),
+            ),
+        };
+        let size = square.size * square.draw_scale();
+        draw_texture_ex(
+            texture,
+            square.x - size / 2.0,
+            square.y - size / 2.0,
+            square.color(palette),
+            DrawTextureParams {
+                dest_size: Some(vec2(size, size)),
+                source: Some(frame.source_rect),
+                ..Default::default()
+            },
+        );
+        if ctx.settings.colorblind {
+            draw_enemy_marker(square, size, palette);
+        }
+    }
+    if let Some(boss) = &ctx.run.boss {
+        draw_texture_ex(
+            &ctx.assets.enemy_big_texture,
+            boss.x - boss.size / 2.0,
+            boss.y - boss.size / 2.0,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(boss.size, boss.size)),
+                source: Some(ctx.sprites.boss.frame().source_rect),
+                ..Default::default()
+            },
+        );
+    }
+    for text in &ctx.run.floating_texts {
+        let (label, color) = if text.value < 0 {
+            (text.value.to_string(), palette.danger)
+        } else {
+            (format!("+{}", text.value), palette.text)
+        };
+        let text_dimensions = measure_font_text(label.as_str(), 20);
+        draw_font_text(
+            label.as_str(),
+            text.x - text_dimensions.width / 2.0,
+            text.y,
+            20.0,
+            Color::new(color.r, color.g, color.b, text.alpha()),
+        );
+    }
+}
+
+/// Draws a shape over an enemy that tells its kind apart without relying on
+/// color: a cross for friendlies, a thick frame for tanks, an arrow for
+/// fast enemies and a crack for splitters.
+fn draw_enemy_marker(enemy: &Enemy, size: f32, palette: &Palette) {
+    let half = size / 2.0;
+    let color = enemy.color(palette);
+    match enemy.kind {
+        EnemyKind::Friendly => {
+            draw_circle_lines(enemy.x, enemy.y, half, 2.0, color);
+            draw_line(enemy.x - half, enemy.y, enemy.x + half, enemy.y, 3.0, color);
+            draw_line(enemy.x, enemy.y - half, enemy.x, enemy.y + half, 3.0, color);
+        }
+        EnemyKind::Tank => {
+            draw_rectangle_lines(enemy.x - half, enemy.y - half, size, size, 5.0, color);
+        }
+        EnemyKind::Fast => {
+            let tip = vec2(enemy.x, enemy.y + half);
+            draw_line(enemy.x - half, enemy.y, tip.x, tip.y, 3.0, color);
+            draw_line(enemy.x + half, enemy.y, tip.x, tip.y, 3.0, color);
+        }
+        EnemyKind::Splitter => {
+            draw_line(
+                enemy.x - half,
+                enemy.y - half,
+                enemy.x + half,
+                enemy.y + half,
+                3.0,
+                color,
+            );
+        }
+        EnemyKind::Grunt => {}
+    }
+}
+
+/// Draws shrinking, fading copies of a bullet back along the way it came,
+/// worked out from its velocity so no past positions need to be stored.
+fn draw_trail(bullet: &Shape, color: Color) {
+    let velocity = vec2(bullet.vx, bullet.vy);
+    for i in 1..=TRAIL_LENGTH {
+        let fade = 1.0 - i as f32 / (TRAIL_LENGTH + 1) as f32;
+        let position = vec2(bullet.x, bullet.y) - velocity * TRAIL_SPACING * i as f32;
+        draw_circle(
+            position.x,
+            position.y,
+            bullet.size / 4.0 * fade,
+            Color::new(color.r, color.g, color.b, 0.5 * fade),
+        );
+    }
+}
+
+/// Offsets the view by a random amount while the screen is shaking, scaled
+/// by the shake intensity chosen in the settings.
+pub fn set_shake_camera(ctx: &GameContext) {
+    let shake = ctx.run.screen_shake * ctx.settings.screen_shake;
+    if ctx.settings.reduce_motion || shake <= 0.0 {
+        return;
+    }
+    let offset = vec2(rand::gen_range(-1.0, 1.0), rand::gen_range(-1.0, 1.0)) * shake;
+    ctx.post_process.set_camera(offset);
+}
+
+/// Draws the ship tilted toward the direction it is steering, scaled so the
+/// sprite is as wide as the player's hitbox.
+fn draw_player(ctx: &GameContext) {
+    let player = &ctx.run.circle;
+    let palette = ctx.palette();
+    let charge = player.charge();
+    if player.charging && charge > 0.0 {
+        draw_circle(
+            player.x,
+            player.y,
+            player.size / 2.0 * (1.0 + charge),
+            Color::new(
+                palette.charge.r,
+                palette.charge.g,
+                palette.charge.b,
+                0.15 + 0.25 * charge,
+            ),
+        );
+    }
+    let Some(ship_texture) = &ctx.assets.ship_texture else {
+        draw_circle(player.x, player.y, player.size / 2.0, palette.player);
+        return;
+    };
+    let ship_frame = ctx.sprites.ship.frame();
+    let size = ship_frame.dest_size * (player.size / ship_frame.dest_size.x);
+    draw_texture_ex(
+        ship_texture,
+        player.x - size.x / 2.0,
+        player.y - size.y / 2.0,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(size),
+            source: Some(ship_frame.source_rect),
+            rotation: SHIP_TILT * player.direction,
+            ..Default::default()
+        },
+    );
+}
+
+/// Draws the running explosions. Drawing an emitter also advances its
+/// particles, so this is only called while the game is running.
+pub fn draw_explosions(ctx: &mut GameContext) {
+    for (explosion, coords) in ctx.explosions.emitters.iter_mut() {
+        explosion.draw(*coords);
+    }
+}
+
+/// Draws the score, lives, power-ups and other status text over the playing field.
+pub fn draw_hud(ctx: &GameContext) {
+    let palette = ctx.palette();
+    let scale = ui_scale();
+    let margin = 10.0 * scale;
+    if ctx.run.mode == GameMode::Practice {
+        // Faint and behind the rest of the HUD, as a reminder that the run
+        // doesn't count
+        let color = palette.text;
+        draw_centered_text(
+            ctx.tr(Text::Practice).to_uppercase().as_str(),
+            screen_height() / 2.0 + 40.0 * scale,
+            PRACTICE_WATERMARK_SIZE,
+            Color::new(color.r, color.g, color.b, PRACTICE_WATERMARK_ALPHA),
+        );
+    }
+    let score_text = format!("{}: {}", ctx.tr(Text::Score), ctx.run.score);
+    let score_dimensions = draw_text_shadowed(
+        score_text.as_str(),
+        margin,
+        35.0 * scale,
+        25.0,
+        palette.text,
+    );
+    if ctx.run.combo > 1 {
+        draw_text_shadowed(
+            format!("x{}", ctx.run.combo).as_str(),
+            score_dimensions.width + 20.0 * scale,
+            35.0 * scale,
+            25.0,
+            palette.combo,
+        );
+        // Runs down until the next kill has to land, and gets brighter the
+        // longer the combo is
+        let intensity = (ctx.run.combo as f32 / COMBO_BAR_FULL as f32).min(1.0);
+        let color = palette.combo;
+        draw_rectangle(
+            margin,
+            41.0 * scale,
+            COMBO_BAR_SIZE.x * scale * ctx.combo_fraction(),
+            COMBO_BAR_SIZE.y * scale,
+            Color::new(color.r, color.g, color.b, 0.35 + 0.65 * intensity),
+        );
+    }
+    for i in 0..ctx.run.circle.lives {
+        draw_circle(
+            screen_width() / 2.0
+                - ((ctx.run.circle.lives - 1) as f32 * 10.0 - i as f32 * 20.0) * scale,
+            28.0 * scale,
+            7.0 * scale,
+            palette.player,
+        );
+    }
+    if ctx.run.mode == GameMode::TimeAttack {
+        // Whole seconds left, so the clock shows 0:00 only once time is up
+        let seconds = ctx.run.time_left.ceil() as u32;
+        let color = if ctx.run.time_left <= TIME_ATTACK_WARNING {
+            palette.danger
+        } else {
+            palette.text
+        };
+        draw_centered_text_shadowed(
+            format!("{}:{:02}", seconds / 60, seconds % 60).as_str(),
+            100.0 * scale,
+            50.0,
+            color,
+        );
+    }
+    if let Some(boss) = &ctx.run.boss {
+        let bar_width = screen_width() / 2.0;
+        let x = screen_width() / 2.0 - bar_width / 2.0;
+        let (y, height) = (45.0 * scale, 12.0 * scale);
+        draw_rectangle(x, y, bar_width * boss.health(), height, palette.danger);
+        draw_rectangle_lines(x, y, bar_width, height, 2.0, palette.text);
+    }
+    for (i, (kind, remaining)) in ctx.run.circle.active_power_ups().into_iter().enumerate() {
+        let y = (60.0 + i as f32 * 20.0) * scale;
+        draw_text_shadowed(kind.label(), margin, y, 20.0, kind.color(palette));
+        draw_rectangle(
+            30.0 * scale,
+            y - 13.0 * scale,
+            100.0 * scale * remaining,
+            10.0 * scale,
+            kind.color(palette),
+        );
+    }
+    draw_text_shadowed(
+        format!("{}: {}", ctx.tr(Text::Bombs), ctx.run.circle.bombs).as_str(),
+        margin,
+        screen_height() - 15.0 * scale,
+        25.0,
+        palette.text,
+    );
+    let highscore_text = format!("{}: {}", ctx.tr(Text::HighScore), ctx.high_score);
+    let text_dimensions = measure_ui_text(highscore_text.as_str(), 25);
+    let highscore_x = screen_width() - text_dimensions.width - margin;
+    // Move the high score down to the bottom corner when the window is too
+    // narrow to fit it next to the score and combo
+    let (highscore_y, stage_y) = if highscore_x < score_dimensions.width + 70.0 * scale {
+        (
+            screen_height() - 15.0 * scale,
+            screen_height() - 40.0 * scale,
+        )
+    } else {
+        (35.0 * scale, 60.0 * scale)
+    };
+    draw_text_shadowed(
+        highscore_text.as_str(),
+        highscore_x,
+        highscore_y,
+        25.0,
+        palette.text,
+    );
+    if ctx.playback.is_some() && get_time().fract() < 0.5 {
+        draw_centered_text_shadowed(
+            ctx.tr(Text::Replay),
+            screen_height() - 40.0 * scale,
+            25.0,
+            palette.danger,
+        );
+    }
+    if let Some(seed) = ctx.seed {
+        let seed_text = format!("{}: {}", ctx.tr(Text::Seed), seed);
+        let text_dimensions = measure_ui_text(seed_text.as_str(), 20);
+        draw_text_shadowed(
+            seed_text.as_str(),
+            screen_width() - text_dimensions.width - margin,
+            if stage_y > highscore_y {
+                stage_y + 20.0 * scale
+            } else {
+                stage_y - 20.0 * scale
+            },
+            20.0,
+            palette.dim_text,
+        );
+    }
+    let stage_text = match ctx.run.mode {
+        GameMode::Endless | GameMode::TimeAttack | GameMode::Practice => {
+            format!(
+                "{} {}",
+                ctx.tr(Text::Stage),
+                ramp_stage(ctx.run.elapsed_time)
+            )
+        }
+        GameMode::Waves => format!("{} {}", ctx.tr(Text::Wave), ctx.run.wave),
+    };
+    let text_dimensions = measure_ui_text(stage_text.as_str(), 20);
+    draw_text_shadowed(
+        stage_text.as_str(),
+        screen_width() - text_dimensions.width - margin,
+        stage_y,
+        20.0,
+        palette.faint_text,
+    );
+}
+
+/// Tints the playing field while a time slow holds the enemies back, fading
+/// along with the slowdown.
+pub fn draw_time_slow_tint(ctx: &GameContext) {
+    let strength = (1.0 - ctx.run.time_scale) / (1.0 - TIME_SLOW_SCALE);
+    if strength <= 0.0 {
+        return;
+    }
+    let color = ctx.palette().time_slow;
+    draw_rectangle(
+        0.0,
+        0.0,
+        screen_width(),
+        screen_height(),
+        Color::new(color.r, color.g, color.b, TIME_SLOW_TINT * strength),
+    );
+}
+
+/// Darkens the screen edges red while the player is down to their last
+/// life, pulsing with each heartbeat unless motion is reduced.
+pub fn draw_low_health_vignette(ctx: &GameContext) {
+    if ctx.run.circle.lives != 1 {
+        return;
+    }
+    let strength = if ctx.settings.reduce_motion {
+        0.6
+    } else {
+        let phase = (ctx.run.circle.clock / HEARTBEAT_INTERVAL).fract() as f32;
+        // Strongest right on the beat, easing off until the next one
+        0.45 + 0.35 * (0.5 + 0.5 * (phase * std::f32::consts::TAU).cos())
+    };
+    let color = ctx.palette().danger;
+    let band = VIGNETTE_WIDTH / VIGNETTE_STEPS as f32;
+    for i in 0..VIGNETTE_STEPS {
+        // Fades out toward the middle of the screen
+        let alpha = strength * (1.0 - i as f32 / VIGNETTE_STEPS as f32) * 0.5;
+        let inset = i as f32 * band;
+        draw_rectangle_lines(
+            inset,
+            inset,
+            screen_width() - inset * 2.0,
+            screen_height() - inset * 2.0,
+            band * 2.0,
+            Color::new(color.r, color.g, color.b, alpha),
+        );
+    }
+}
+
+/// Flashes a warning across the screen and marks where the boss is about to
+/// come in.
+pub fn draw_boss_warning(ctx: &GameContext) {
+    let palette = ctx.palette();
+    let color = palette.danger;
+    let alpha = if ctx.settings.reduce_motion {
+        0.8
+    } else {
+        0.5 + 0.5 * (get_time() as f32 * BOSS_WARNING_PULSE).sin().abs()
+    };
+    let zone = Boss::spawn_zone();
+    draw_rectangle(
+        zone.x,
+        zone.y,
+        zone.w,
+        zone.h,
+        Color::new(color.r, color.g, color.b, 0.2 * alpha),
+    );
+    draw_rectangle_lines(
+        zone.x,
+        zone.y,
+        zone.w,
+        zone.h,
+        2.0,
+        Color::new(color.r, color.g, color.b, alpha),
+    );
+    let y = screen_height() / 3.0;
+    draw_rectangle(
+        0.0,
+        y - 55.0 * ui_scale(),
+        screen_width(),
+        75.0 * ui_scale(),
+        Color::new(0.0, 0.0, 0.0, 0.5 * alpha),
+    );
+    let text = ctx.tr(Text::Warning);
+    let text_dimensions = measure_ui_text(text, 60);
+    draw_ui_text(
+        text,
+        screen_width() / 2.0 - text_dimensions.width / 2.0,
+        y,
+        60.0,
+        Color::new(color.r, color.g, color.b, alpha),
+    );
+}
+
+/// Announces the coming wave during the break before it, fading out as the
+/// wave starts.
+pub fn draw_wave_banner(ctx: &GameContext) {
+    let text = format!("{} {}", ctx.tr(Text::Wave), ctx.run.wave);
+    let color = ctx.palette().highlight;
+    let alpha = ctx.run.wave_break.min(1.0);
+    draw_centered_text(
+        text.as_str(),
+        screen_height() / 2.0 - 40.0 * ui_scale(),
+        70.0,
+        Color::new(color.r, color.g, color.b, alpha),
+    );
+}
+
+/// Draws the hitboxes used for collisions along with the frame rate and
+/// entity counts, to help tune the game.
+pub fn draw_debug_overlay(ctx: &GameContext) {
+    let hitboxes = ctx
+        .run
+        .squares
+        .iter()
+        .map(|square| (square.rect(), RED))
+        .chain(ctx.run.bullets.iter().map(|bullet| (bullet.rect(), YELLOW)))
+        .chain(
+            ctx.run
+                .enemy_bullets
+                .iter()
+                .map(|bullet| (bullet.rect(), SKYBLUE)),
+        )
+        .chain(ctx.run.coins.iter().map(|coin| (coin.rect(), GOLD)))
+        .chain(
+            ctx.run
+                .power_ups
+                .iter()
+                .map(|power_up| (power_up.rect(), LIME)),
+        )
+        .chain(ctx.run.boss.iter().map(|boss| (boss.rect(), RED)))
+        .chain(std::iter::once((ctx.run.circle.rect(), GREEN)));
+    for (rect, color) in hitboxes {
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 1.0, color);
+    }
+
+    let lines = [
+        format!("FPS: {}", get_fps()),
+        format!("{}: {}", ctx.tr(Text::Enemies), ctx.run.squares.len()),
+        format!(
+            "{}: {}",
+            ctx.tr(Text::Bullets),
+            ctx.run.bullets.iter().count()
+        ),
+        format!(
+            "{}: {}",
+            ctx.tr(Text::Explosions),
+            ctx.explosions.emitters.len()
+        ),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        draw_ui_text(
+            line.as_str(),
+            10.0 * ui_scale(),
+            screen_height() - (60.0 + i as f32 * 20.0) * ui_scale(),
+            20.0,
+            GREEN,
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+
+use crate::gamepad::{Button, Gamepads};
+use crate::key_bindings::{Action, KeyBindings};
+use crate::settings::{ControlScheme, Difficulty, GameMode};
+use crate::storage::write_atomically;
+
+const REPLAY_FILE: &str = "replay.json";
+
+/// The player's input during one frame of a run, along with how long the
+/// frame was so the run can be stepped through exactly as it was played.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub delta_time: f32,
+    /// Horizontal movement, from -1.0 left to 1.0 right
+    pub move_x: f32,
+    /// Vertical movement, from -1.0 up to 1.0 down
+    pub move_y: f32,
+    pub fire: bool,
+    /// Direction to fire in, as a unit vector
+    pub aim_x: f32,
+    pub aim_y: f32,
+    pub bomb: bool,
+}
+
+impl InputFrame {
+    /// Reads the keyboard, mouse and gamepads for this frame, aiming from
+    /// `ship`.
+    pub fn read(
+        delta_time: f32,
+        control_scheme: ControlScheme,
+        bindings: &KeyBindings,
+        gamepads: &Gamepads,
+        ship: Vec2,
+    ) -> Self {
+        let axis = |negative: bool, positive: bool| positive as i32 as f32 - negative as i32 as f32;
+        let (fire, aim) = match control_scheme {
+            ControlScheme::Keyboard => (is_key_down(bindings.fire), vec2(0.0, -1.0)),
+            ControlScheme::Mouse => (
+                is_mouse_button_down(MouseButton::Left),
+                (Vec2::from(mouse_position()) - ship)
+                    .try_normalize()
+                    .unwrap_or(vec2(0.0, -1.0)),
+            ),
+        };
+        let movement = (vec2(
+            axis(
+                bindings.is_down(Action::MoveLeft),
+                bindings.is_down(Action::MoveRight),
+            ),
+            axis(
+                bindings.is_down(Action::MoveUp),
+                bindings.is_down(Action::MoveDown),
+            ),
+        ) + gamepads.movement())
+        .clamp(vec2(-1.0, -1.0), vec2(1.0, 1.0));
+        Self {
+            delta_time,
+            move_x: movement.x,
+            move_y: movement.y,
+            fire: fire || gamepads.is_down(Button::South),
+            aim_x: aim.x,
+            aim_y: aim.y,
+            bomb: is_key_pressed(bindings.bomb) || gamepads.is_pressed(Button::East),
+        }
+    }
+
+    pub fn movement(&self) -> Vec2 {
+        vec2(self.move_x, self.move_y)
+    }
+
+    pub fn aim(&self) -> Vec2 {
+        vec2(self.aim_x, self.aim_y)
+    }
+}
+
+/// A recorded run. Played back with the same seed and settings, the inputs
+/// reproduce the run exactly, as long as the window is the same size.
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+    pub inertia: bool,
+    pub frames: Vec<InputFrame>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, difficulty: Difficulty, mode: GameMode, inertia: bool) -> Self {
+        Self {
+            seed,
+            difficulty,
+            mode,
+            inertia,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Reads the last saved replay, if there is one that can be parsed.
+    pub fn load() -> Option<Self> {
+        let contents = fs::read_to_string(REPLAY_FILE).ok()?;
+        serde_json::from_str(&contents)
+            .map_err(|err| eprintln!("Warning: couldn't read {} ({})", REPLAY_FILE, err))
+            .ok()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let contents = serde_json::to_string(self)?;
+        write_atomically(REPLAY_FILE, &contents)
+    }
+}
+
+/// A replay being played back, feeding its inputs to the game one frame at
+/// a time.
+pub struct Playback {
+    replay: Replay,
+    next_frame: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_frame: 0,
+        }
+    }
+
+    /// Returns the input of the next frame, or `None` once the replay has
+    /// run out.
+    pub fn next_input(&mut self) -> Option<InputFrame> {
+        let frame = self.replay.frames.get(self.next_frame).copied();
+        self.next_frame += 1;
+        frame
+    }
+}
@@ -0,0 +1,456 @@
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+
+use crate::key_bindings::KeyBindings;
+use crate::language::{Language, Text};
+use crate::palette::Theme;
+use crate::storage::write_atomically;
+
+const SETTINGS_FILE: &str = "settings.json";
+/// Step size of the volume and other sliders
+const FRACTION_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// Seconds between enemy spawns.
+    pub fn spawn_interval(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 0.35,
+            Difficulty::Hard => 0.25,
+        }
+    }
+
+    pub fn enemy_speed_scale(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+        }
+    }
+
+    /// Most enemies on screen at once. New ones are held back until there
+    /// is room, so the screen can't fill up beyond escape.
+    pub fn max_enemies(self) -> usize {
+        match self {
+            Difficulty::Easy => 25,
+            Difficulty::Normal => 35,
+            Difficulty::Hard => 50,
+        }
+    }
+
+    /// Points between each extra life, so harder difficulties hand them out
+    /// less often.
+    pub fn extra_life_interval(self) -> u32 {
+        match self {
+            Difficulty::Easy => 800,
+            Difficulty::Normal => 1000,
+            Difficulty::Hard => 1500,
+        }
+    }
+
+    /// Applies the score bonus for playing on a harder difficulty.
+    pub fn scale_score(self, points: u32) -> u32 {
+        let percent = match self {
+            Difficulty::Easy => 75,
+            Difficulty::Normal => 100,
+            Difficulty::Hard => 150,
+        };
+        points * percent / 100
+    }
+
+    pub fn label(self) -> Text {
+        match self {
+            Difficulty::Easy => Text::Easy,
+            Difficulty::Normal => Text::Normal,
+            Difficulty::Hard => Text::Hard,
+        }
+    }
+}
+
+/// The rules a run is played by.
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Enemies keep coming, faster and faster, until the ship is destroyed
+    #[default]
+    Endless,
+    /// Fixed waves of enemies with a short break after each one is cleared
+    Waves,
+    /// As much score as possible before the clock runs out
+    TimeAttack,
+    /// Endless enemies that push the ship away instead of destroying it, to
+    /// learn their patterns without the run counting
+    Practice,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Endless,
+        GameMode::Waves,
+        GameMode::TimeAttack,
+        GameMode::Practice,
+    ];
+    /// The modes with leaderboards
+    pub const RANKED: [GameMode; 3] = [GameMode::Endless, GameMode::Waves, GameMode::TimeAttack];
+
+    pub fn label(self) -> Text {
+        match self {
+            GameMode::Endless => Text::Endless,
+            GameMode::Waves => Text::Waves,
+            GameMode::TimeAttack => Text::TimeAttack,
+            GameMode::Practice => Text::Practice,
+        }
+    }
+
+    /// Whether runs count toward the leaderboards, lifetime stats and
+    /// achievements.
+    pub fn is_ranked(self) -> bool {
+        self != GameMode::Practice
+    }
+}
+
+/// How the player aims and fires. Movement is always on the keyboard.
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ControlScheme {
+    /// Fire straight up with space
+    #[default]
+    Keyboard,
+    /// Fire toward the cursor with the left mouse button
+    Mouse,
+}
+
+impl ControlScheme {
+    pub const ALL: [ControlScheme; 2] = [ControlScheme::Keyboard, ControlScheme::Mouse];
+
+    pub fn label(self) -> Text {
+        match self {
+            ControlScheme::Keyboard => Text::Keyboard,
+            ControlScheme::Mouse => Text::Mouse,
+        }
+    }
+}
+
+/// Highest frame rate the game runs at, to save power on fast monitors.
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum FrameCap {
+    Fps60,
+    Fps120,
+    #[default]
+    Unlimited,
+}
+
+impl FrameCap {
+    pub const ALL: [FrameCap; 3] = [FrameCap::Fps60, FrameCap::Fps120, FrameCap::Unlimited];
+
+    /// Frames per second, or `None` for no cap.
+    pub fn fps(self) -> Option<u32> {
+        match self {
+            FrameCap::Fps60 => Some(60),
+            FrameCap::Fps120 => Some(120),
+            FrameCap::Unlimited => None,
+        }
+    }
+}
+
+/// Where the seed for the random enemy spawns and drops of a run comes from.
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum SeedMode {
+    /// A new seed every run
+    #[default]
+    Random,
+    /// The same seed for everyone on the same day
+    Daily,
+    /// The seed typed in on the settings screen
+    Custom,
+}
+
+impl SeedMode {
+    pub const ALL: [SeedMode; 3] = [SeedMode::Random, SeedMode::Daily, SeedMode::Custom];
+}
+
+/// Preferences that are remembered between runs of the game.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub audio_enabled: bool,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub difficulty: Difficulty,
+    /// Mode picked on the main menu
+    pub game_mode: GameMode,
+    pub control_scheme: ControlScheme,
+    /// Whether the ship speeds up and drifts to a stop instead of starting
+    /// and stopping instantly
+    pub inertia: bool,
+    pub seed_mode: SeedMode,
+    pub custom_seed: u64,
+    /// Whether each run is saved to the replay file when it ends
+    pub record_replay: bool,
+    pub show_fps: bool,
+    pub frame_cap: FrameCap,
+    pub fullscreen: bool,
+    /// Size the window opens at, in logical pixels. Follows the window
+    /// whenever it is resized outside of fullscreen.
+    pub window_width: u32,
+    pub window_height: u32,
+    pub language: Language,
+    /// How strongly explosions shake the screen, from 0.0 (not at all) to 1.0
+    pub screen_shake: f32,
+    /// Tones down screen shake, starfield warp and explosions without
+    /// changing the gameplay
+    pub reduce_motion: bool,
+    /// How strongly the starfield leans while steering, from -1.0 (fully
+    /// the other way) to 1.0 (the normal way)
+    pub star_scroll_scale: f32,
+    /// Draws scanlines and dark corners over the screen like an old monitor
+    pub crt_effect: bool,
+    pub theme: Theme,
+    /// Marks each kind of enemy with its own shape, so they can be told
+    /// apart without relying on color
+    pub colorblind: bool,
+    pub key_bindings: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            audio_enabled: true,
+            music_volume: 0.3,
+            sfx_volume: 1.0,
+            difficulty: Difficulty::Normal,
+            game_mode: GameMode::Endless,
+            control_scheme: ControlScheme::Keyboard,
+            inertia: false,
+            seed_mode: SeedMode::Random,
+            custom_seed: 0,
+            record_replay: false,
+            show_fps: false,
+            frame_cap: FrameCap::Unlimited,
+            fullscreen: false,
+            window_width: 800,
+            window_height: 600,
+            language: Language::Swedish,
+            screen_shake: 1.0,
+            reduce_motion: false,
+            star_scroll_scale: 1.0,
+            crt_effect: false,
+            theme: Theme::Classic,
+            colorblind: false,
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings file, falling back to the defaults if it is missing
+    /// or can't be parsed.
+    pub fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(SETTINGS_FILE) else {
+            return Self::default();
+        };
+        let mut settings: Self = serde_json::from_str(&contents).unwrap_or_else(|err| {
+            eprintln!(
+                "Warning: couldn't read {} ({}), using default settings",
+                SETTINGS_FILE, err
+            );
+            Self::default()
+        });
+        if !settings.key_bindings.is_valid() {
+            eprintln!(
+                "Warning: two actions share a key in {}, using the default keys",
+                SETTINGS_FILE
+            );
+            settings.key_bindings = KeyBindings::default();
+        }
+        settings
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        write_atomically(SETTINGS_FILE, &contents)
+    }
+
+    /// The seed to start the next run with, or `None` if it should be random.
+    pub fn run_seed(&self) -> Option<u64> {
+        match self.seed_mode {
+            SeedMode::Random => None,
+            // Days since the Unix epoch
+            SeedMode::Daily => Some((macroquad::miniquad::date::now() / 86_400.0) as u64),
+            SeedMode::Custom => Some(self.custom_seed),
+        }
+    }
+
+    /// Changes a setting one step backwards (-1) or forwards (1).
+    pub fn adjust(&mut self, item: SettingsItem, direction: i32) {
+        let step_fraction = |fraction: f32| {
+            ((fraction / FRACTION_STEP).round() + direction as f32).clamp(0.0, 1.0 / FRACTION_STEP)
+                * FRACTION_STEP
+        };
+        match item {
+            SettingsItem::Audio => self.audio_enabled = !self.audio_enabled,
+            SettingsItem::MusicVolume => self.music_volume = step_fraction(self.music_volume),
+            SettingsItem::SfxVolume => self.sfx_volume = step_fraction(self.sfx_volume),
+            SettingsItem::ScreenShake => self.screen_shake = step_fraction(self.screen_shake),
+            SettingsItem::StarScroll => {
+                let steps = 1.0 / FRACTION_STEP;
+                self.star_scroll_scale = ((self.star_scroll_scale / FRACTION_STEP).round()
+                    + direction as f32)
+                    .clamp(-steps, steps)
+                    * FRACTION_STEP
+            }
+            SettingsItem::Seed => self.seed_mode = cycle(&SeedMode::ALL, self.seed_mode, direction),
+            SettingsItem::Controls => {
+                self.control_scheme = cycle(&ControlScheme::ALL, self.control_scheme, direction)
+            }
+            SettingsItem::Difficulty => {
+                self.difficulty = cycle(&Difficulty::ALL, self.difficulty, direction)
+            }
+            SettingsItem::Inertia => self.inertia = !self.inertia,
+            SettingsItem::ReduceMotion => self.reduce_motion = !self.reduce_motion,
+            SettingsItem::CrtEffect => self.crt_effect = !self.crt_effect,
+            SettingsItem::Theme => self.theme = cycle(&Theme::ALL, self.theme, direction),
+            SettingsItem::Colorblind => self.colorblind = !self.colorblind,
+            SettingsItem::RecordReplay => self.record_replay = !self.record_replay,
+            SettingsItem::ShowFps => self.show_fps = !self.show_fps,
+            SettingsItem::FrameCap => {
+                self.frame_cap = cycle(&FrameCap::ALL, self.frame_cap, direction)
+            }
+            SettingsItem::Language => {
+                self.language = cycle(&Language::ALL, self.language, direction)
+            }
+        }
+    }
+
+    pub fn value(&self, item: SettingsItem) -> SettingValue {
+        let text = |text| SettingValue::Text(self.language.tr(text));
+        match item {
+            SettingsItem::Audio => text(on_off(self.audio_enabled)),
+            SettingsItem::MusicVolume => SettingValue::Slider(self.music_volume),
+            SettingsItem::SfxVolume => SettingValue::Slider(self.sfx_volume),
+            SettingsItem::Difficulty => text(self.difficulty.label()),
+            SettingsItem::Controls => text(self.control_scheme.label()),
+            SettingsItem::Inertia => text(on_off(self.inertia)),
+            SettingsItem::Seed => match self.seed_mode {
+                SeedMode::Random => text(Text::RandomSeed),
+                SeedMode::Daily => text(Text::DailySeed),
+                SeedMode::Custom => SettingValue::Number(self.custom_seed),
+            },
+            SettingsItem::ScreenShake => SettingValue::Slider(self.screen_shake),
+            SettingsItem::ReduceMotion => text(on_off(self.reduce_motion)),
+            SettingsItem::StarScroll => SettingValue::Percent(self.star_scroll_scale),
+            SettingsItem::CrtEffect => text(on_off(self.crt_effect)),
+            SettingsItem::Theme => text(self.theme.label()),
+            SettingsItem::Colorblind => text(on_off(self.colorblind)),
+            SettingsItem::RecordReplay => text(on_off(self.record_replay)),
+            SettingsItem::ShowFps => text(on_off(self.show_fps)),
+            SettingsItem::FrameCap => match self.frame_cap.fps() {
+                Some(fps) => SettingValue::Number(fps as u64),
+                None => text(Text::Unlimited),
+            },
+            SettingsItem::Language => SettingValue::Text(self.language.label()),
+        }
+    }
+}
+
+/// The rows of the settings screen.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SettingsItem {
+    Audio,
+    MusicVolume,
+    SfxVolume,
+    Difficulty,
+    Controls,
+    Inertia,
+    Seed,
+    RecordReplay,
+    ScreenShake,
+    ReduceMotion,
+    StarScroll,
+    CrtEffect,
+    Theme,
+    Colorblind,
+    ShowFps,
+    FrameCap,
+    Language,
+}
+
+impl SettingsItem {
+    pub const ALL: [SettingsItem; 17] = [
+        SettingsItem::Audio,
+        SettingsItem::MusicVolume,
+        SettingsItem::SfxVolume,
+        SettingsItem::Difficulty,
+        SettingsItem::Controls,
+        SettingsItem::Inertia,
+        SettingsItem::Seed,
+        SettingsItem::RecordReplay,
+        SettingsItem::ScreenShake,
+        SettingsItem::ReduceMotion,
+        SettingsItem::StarScroll,
+        SettingsItem::CrtEffect,
+        SettingsItem::Theme,
+        SettingsItem::Colorblind,
+        SettingsItem::ShowFps,
+        SettingsItem::FrameCap,
+        SettingsItem::Language,
+    ];
+
+    pub fn label(self) -> Text {
+        match self {
+            SettingsItem::Audio => Text::Audio,
+            SettingsItem::MusicVolume => Text::MusicVolume,
+            SettingsItem::SfxVolume => Text::SfxVolume,
+            SettingsItem::Difficulty => Text::Difficulty,
+            SettingsItem::Controls => Text::Controls,
+            SettingsItem::Inertia => Text::Inertia,
+            SettingsItem::Seed => Text::Seed,
+            SettingsItem::RecordReplay => Text::RecordReplay,
+            SettingsItem::ScreenShake => Text::ScreenShake,
+            SettingsItem::ReduceMotion => Text::ReduceMotion,
+            SettingsItem::StarScroll => Text::StarScroll,
+            SettingsItem::CrtEffect => Text::CrtEffect,
+            SettingsItem::Theme => Text::Theme,
+            SettingsItem::Colorblind => Text::Colorblind,
+            SettingsItem::ShowFps => Text::ShowFps,
+            SettingsItem::FrameCap => Text::FrameCap,
+            SettingsItem::Language => Text::Language,
+        }
+    }
+}
+
+/// How a setting's current value is shown on the settings screen.
+pub enum SettingValue {
+    Text(&'static str),
+    /// A fraction between 0.0 and 1.0 drawn as a bar
+    Slider(f32),
+    Number(u64),
+    /// A fraction between -1.0 and 1.0 shown as a percentage
+    Percent(f32),
+}
+
+fn on_off(enabled: bool) -> Text {
+    if enabled {
+        Text::On
+    } else {
+        Text::Off
+    }
+}
+
+/// Returns the option before (-1) or after (1) the current one, wrapping around.
+pub fn cycle<T: Copy + PartialEq>(options: &[T], current: T, direction: i32) -> T {
+    let index = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0) as i32;
+    options[(index + direction).rem_euclid(options.len() as i32) as usize]
+}
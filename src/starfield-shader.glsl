@@ -13,6 +13,7 @@ varying float iTime;
 
 uniform vec2 iResolution;
 uniform float direction_modifier;
+uniform float warp_intensity;
 
 #define NUM_LAYERS 4.
 
@@ -73,6 +74,8 @@ void main()
     float speed = 3.0;
     vec2 direction = vec2(-0.25 + direction_modifier, -1.0) * speed;
 
+    // Stretch the stars out along the direction of travel as the warp picks up
+    uv.y /= 1. + warp_intensity * 3.;
     uv += direction;
     vec3 col = vec3(0);
 
@@ -83,5 +86,7 @@ void main()
         col += StarLayer(uv * scale + i * 453.2) * fade;
     }
 
+    col *= 1. + warp_intensity * .5;
+
     gl_FragColor = vec4(col, 1.0);
 }
@@ -0,0 +1,2453 @@
+use macroquad::prelude::*;
+use macroquad_particles::{Emitter, EmitterConfig};
+
+use crate::achievements::{Achievement, AchievementTracker};
+use crate::audio::AudioManager;
+use crate::collision::CollisionGrid;
+use crate::entities::{
+    Boss, BulletPool, Collider, Enemy, EnemyKind, FloatingText, Movement, Player, PowerUp,
+    PowerUpKind, Shape, SpawnColumns, Trigger, Weapon, MAX_LIVES,
+};
+use crate::gamepad::{Button, Gamepads};
+use crate::key_bindings::{key_name, Action};
+use crate::language::Text;
+use crate::palette::Palette;
+use crate::render::{
+    boss_explosion, button_clicked, button_column, button_row, draw_boss_warning, draw_button,
+    draw_centered_text, draw_dim_overlay, draw_explosions, draw_hud, draw_low_health_vignette,
+    draw_tabs, draw_time_slow_tint, draw_ui_text, draw_wave_banner, draw_world, enemy_explosion,
+    measure_ui_text, muzzle_flash, player_explosion, set_shake_camera, ui_scale, Assets,
+    Explosions, PostProcess, Sprites, Toast, MAX_TOASTS,
+};
+use crate::replay::{InputFrame, Playback, Replay};
+use crate::settings::{
+    cycle, ControlScheme, Difficulty, GameMode, SeedMode, SettingValue, Settings, SettingsItem,
+};
+use crate::stats::{LifetimeStats, RunStats};
+use crate::storage::{Leaderboard, Leaderboards};
+use crate::waves::{wave_enemies, wave_speed_scale, WAVE_BONUS, WAVE_BREAK_TIME};
+
+const MAX_FRAME_TIME: f32 = 0.05;
+/// A frame longer than this means the game stopped being updated, most
+/// likely because the window lost focus, so the run is paused
+const FOCUS_LOSS_FRAME_TIME: f32 = 0.5;
+/// Seconds the window has to keep its size before the size is saved, so
+/// dragging its edge doesn't write the settings on every frame
+const WINDOW_SIZE_SAVE_DELAY: f32 = 0.5;
+const RESPAWN_CLEAR_RADIUS: f32 = 150.0;
+/// Seconds after a kill in which the next kill keeps the combo going
+const COMBO_WINDOW: f32 = 1.5;
+/// Longest name that can be typed in for the leaderboard
+const MAX_NAME_LENGTH: usize = 8;
+/// How quickly the magnet can change a coin's velocity, in pixels per second squared
+const MAGNET_PULL: f32 = 900.0;
+const MAGNET_MAX_SPEED: f32 = 400.0;
+/// Angle in radians between the middle and side bullets of a spread shot
+const SPREAD_ANGLE: f32 = 0.25;
+/// Seconds into a run until enemies spawn and move at their fastest
+const RAMP_DURATION: f32 = 120.0;
+/// Seconds a time attack run lasts
+const TIME_ATTACK_DURATION: f32 = 60.0;
+/// Number of difficulty stages shown in the HUD over the course of the ramp
+const RAMP_STAGES: u32 = 5;
+/// How much shorter the spawn interval gets at the end of the ramp
+const RAMP_SPAWN_REDUCTION: f32 = 0.5;
+/// How much faster enemies move at the end of the ramp
+const RAMP_SPEED_INCREASE: f32 = 0.5;
+/// One in this many destroyed enemies drops a coin
+const COIN_DROP_ODDS: u32 = 3;
+const COIN_SPEED: f32 = 100.0;
+const COIN_SCORE: u32 = 25;
+/// A boss appears every time the score passes another multiple of this
+const BOSS_SCORE_INTERVAL: u32 = 2000;
+/// Score bonus for destroying a boss
+const BOSS_SCORE: u32 = 1000;
+/// Seconds the warning is shown before the boss arrives
+const BOSS_WARNING_TIME: f32 = 2.5;
+/// Largest starfield warp in either direction
+const MAX_DIRECTION_MODIFIER: f32 = 1.0;
+/// Largest starfield warp when the player has asked for reduced motion
+const REDUCED_MAX_DIRECTION_MODIFIER: f32 = 0.25;
+/// How fast the starfield warp returns to zero without sideways input, per second
+const DIRECTION_MODIFIER_DECAY: f32 = 0.1;
+/// Starfield warp intensity while the ship moves at full speed
+const MOVE_WARP: f32 = 0.6;
+/// Extra warp intensity while firing
+const FIRE_WARP: f32 = 0.4;
+/// Largest warp intensity when the player has asked for reduced motion
+const REDUCED_MAX_WARP: f32 = 0.25;
+/// How quickly the warp intensity follows the ship, per second
+const WARP_EASE: f32 = 3.0;
+/// Seconds between heartbeats while the player is down to their last life
+pub const HEARTBEAT_INTERVAL: f64 = 0.9;
+/// How fast the enemies and their bullets move during a time slow
+pub const TIME_SLOW_SCALE: f32 = 0.4;
+/// How quickly the speed of the world eases in and out of a time slow
+const TIME_SCALE_EASE: f32 = 4.0;
+/// Width and height of a cell in the grid used for bullet collisions
+const COLLISION_CELL_SIZE: f32 = 64.0;
+/// Strongest screen shake, as the largest offset in pixels
+const MAX_SCREEN_SHAKE: f32 = 20.0;
+/// How quickly the screen shake dies down, as a fraction per second
+const SCREEN_SHAKE_DECAY: f32 = 6.0;
+const ENEMY_EXPLOSION_SHAKE: f32 = 4.0;
+const BOMB_SHAKE: f32 = 16.0;
+const BOSS_EXPLOSION_SHAKE: f32 = 14.0;
+/// How many times its usual score shooting a friendly costs
+const FRIENDLY_PENALTY_MULTIPLIER: u32 = 2;
+/// Whether flying into a friendly costs a life like any other enemy
+const FRIENDLIES_HURT: bool = false;
+/// Score for every enemy or bullet that passes close by without hitting
+const GRAZE_SCORE: u32 = 5;
+/// Seconds the ring around the ship shows after a graze
+const GRAZE_PULSE_TIME: f32 = 0.3;
+/// Extra hit points a fully charged shot knocks off
+const CHARGED_EXTRA_DAMAGE: u32 = 3;
+/// Extra enemies a fully charged shot goes through
+const CHARGED_EXTRA_PIERCE: u32 = 3;
+/// Radius of the blast from a destroyed enemy, relative to its size
+const BLAST_RADIUS_PER_SIZE: f32 = 1.25;
+/// Most blasts in a row a single kill can set off
+const MAX_CHAIN_LENGTH: u32 = 3;
+/// How much the frozen playing field is darkened behind the pause menu,
+/// from 0.0 (not at all) to 1.0 (black)
+const PAUSE_DIM: f32 = 0.6;
+/// Seconds it takes to fade out to black, and again to fade back in
+const FADE_TIME: f32 = 0.15;
+/// Seconds each step of the countdown before a run is shown
+const COUNTDOWN_STEP: f32 = 0.7;
+/// Seconds the player's death plays out before the game over screen
+const DEATH_TIME: f32 = 1.5;
+/// How fast the world moves while the player's death plays out
+const DEATH_TIME_SCALE: f32 = 0.25;
+/// One in this many destroyed enemies drops a power-up
+const POWER_UP_DROP_ODDS: u32 = 12;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameState {
+    MainMenu,
+    /// Counts down before a new run starts
+    Countdown,
+    Playing,
+    Paused,
+    /// Plays the player's death in slow motion before the game over screen
+    Dying,
+    GameOver,
+    EnterName,
+    Leaderboard,
+    Settings,
+    KeyBindings,
+    Stats,
+}
+
+impl GameState {
+    /// Whether switching from this state to the next one fades through black.
+    /// Pausing, dying and the end of the countdown switch instantly so the
+    /// action isn't interrupted.
+    pub fn fades_to(self, next: GameState) -> bool {
+        !matches!(
+            (self, next),
+            (GameState::Playing, GameState::Paused)
+                | (GameState::Paused, GameState::Playing)
+                | (GameState::Countdown, GameState::Playing)
+                | (GameState::Playing, GameState::Dying)
+        )
+    }
+}
+
+/// Fades the screen out to black and back in when switching between states.
+/// The screens get no input while a fade is running, so a held key can't
+/// skip past the next screen.
+pub struct Transition {
+    /// Opacity of the black overlay
+    transition_alpha: f32,
+    /// State to switch to once the screen has faded out
+    pending: Option<GameState>,
+}
+
+impl Transition {
+    pub fn new() -> Self {
+        Self {
+            transition_alpha: 0.0,
+            pending: None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.pending.is_some() || self.transition_alpha > 0.0
+    }
+
+    pub fn start(&mut self, next: GameState) {
+        self.pending = Some(next);
+    }
+
+    /// Advances the fade, returning the state to switch to once the screen
+    /// has gone fully black.
+    pub fn update(&mut self) -> Option<GameState> {
+        let step = get_frame_time().min(MAX_FRAME_TIME) / FADE_TIME;
+        if self.pending.is_some() {
+            self.transition_alpha = (self.transition_alpha + step).min(1.0);
+            if self.transition_alpha >= 1.0 {
+                return self.pending.take();
+            }
+        } else {
+            self.transition_alpha = (self.transition_alpha - step).max(0.0);
+        }
+        None
+    }
+
+    pub fn draw(&self) {
+        if self.transition_alpha > 0.0 {
+            draw_dim_overlay(self.transition_alpha);
+        }
+    }
+}
+
+/// How far into the difficulty ramp a run is, from 0.0 at the start to 1.0
+/// once it has lasted `RAMP_DURATION` seconds.
+fn ramp_progress(elapsed_time: f32) -> f32 {
+    (elapsed_time / RAMP_DURATION).min(1.0)
+}
+
+/// The difficulty stage shown in the HUD, counting from 1.
+pub fn ramp_stage(elapsed_time: f32) -> u32 {
+    ((ramp_progress(elapsed_time) * RAMP_STAGES as f32) as u32 + 1).min(RAMP_STAGES)
+}
+
+/// Where the ship starts a run.
+fn screen_center() -> Vec2 {
+    vec2(screen_width() / 2.0, screen_height() / 2.0)
+}
+
+/// Everything that belongs to a single run: the playing field, the score
+/// and the effects that follow the action. Kept apart from the rest of the
+/// game so it can be put back to the start without a window.
+pub struct Run {
+    pub squares: Vec<Enemy>,
+    pub bullets: BulletPool,
+    pub enemy_bullets: Vec<Shape>,
+    pub power_ups: Vec<PowerUp>,
+    pub coins: Vec<Shape>,
+    /// Score popups from recent kills
+    pub floating_texts: Vec<FloatingText>,
+    /// Enemies bucketed by position, rebuilt every frame before checking bullet hits
+    pub collision_grid: CollisionGrid,
+    pub circle: Player,
+    pub boss: Option<Boss>,
+    /// Seconds until the boss arrives while its warning is shown
+    pub boss_warning: Option<f32>,
+    pub next_boss_score: u32,
+    /// Score at which the next extra life is awarded
+    pub next_extra_life_score: u32,
+    /// Id handed to the most recently spawned enemy
+    pub next_enemy_id: u32,
+    pub spawn_timer: f32,
+    pub spawn_columns: SpawnColumns,
+    pub elapsed_time: f32,
+    /// Difficulty of the current run, picked up from the settings when it starts
+    pub difficulty: Difficulty,
+    /// Mode of the current run, picked up from the settings when it starts
+    pub mode: GameMode,
+    /// Whether the ship moves with inertia in the current run, picked up
+    /// from the settings when it starts
+    pub inertia: bool,
+    /// The wave in progress in wave mode, or coming up during a break
+    pub wave: u32,
+    /// Seconds left of the break before the next wave, 0.0 while a wave is
+    /// in progress
+    pub wave_break: f32,
+    /// Enemies of the current wave that have yet to spawn
+    pub wave_enemies: Vec<(EnemyKind, Movement)>,
+    /// Seconds left on the clock in time attack mode
+    pub time_left: f32,
+    pub score: u32,
+    pub combo: u32,
+    pub combo_timer: f32,
+    pub run_stats: RunStats,
+    pub direction_modifier: f32,
+    /// How stretched the starfield is, from 0.0 when the ship is idle to 1.0
+    /// when it is moving and firing flat out
+    pub warp_intensity: f32,
+    /// How fast the enemies and their bullets move compared to the ship,
+    /// eased down to `TIME_SLOW_SCALE` during a time slow and back up after
+    pub time_scale: f32,
+    /// Number of the last heartbeat, counted on the player's clock, so each
+    /// one is heard once on the last life
+    pub last_heartbeat: u64,
+    /// How far the view is currently jolted by explosions, in pixels
+    pub screen_shake: f32,
+    /// Strength of the ring drawn around the ship after a graze, fading from 1.0
+    pub graze_pulse: f32,
+}
+
+impl Run {
+    /// A run on the difficulty, mode and inertia picked in the settings,
+    /// with the ship at `spawn`.
+    pub fn new(settings: &Settings, spawn: Vec2) -> Self {
+        Self {
+            squares: vec![],
+            bullets: BulletPool::new(),
+            enemy_bullets: vec![],
+            power_ups: vec![],
+            coins: vec![],
+            floating_texts: vec![],
+            collision_grid: CollisionGrid::new(COLLISION_CELL_SIZE),
+            circle: Player::new(spawn),
+            boss: None,
+            boss_warning: None,
+            next_boss_score: BOSS_SCORE_INTERVAL,
+            next_extra_life_score: settings.difficulty.extra_life_interval(),
+            next_enemy_id: 0,
+            spawn_timer: 0.0,
+            spawn_columns: SpawnColumns::new(),
+            elapsed_time: 0.0,
+            difficulty: settings.difficulty,
+            mode: settings.game_mode,
+            inertia: settings.inertia,
+            wave: 1,
+            wave_break: WAVE_BREAK_TIME,
+            wave_enemies: Vec::new(),
+            time_left: TIME_ATTACK_DURATION,
+            score: 0,
+            combo: 1,
+            combo_timer: 0.0,
+            run_stats: RunStats::default(),
+            direction_modifier: 0.0,
+            warp_intensity: 0.0,
+            time_scale: 1.0,
+            last_heartbeat: 0,
+            screen_shake: 0.0,
+            graze_pulse: 0.0,
+        }
+    }
+
+    /// Starts over with a fresh run like `new` does, but keeps the bullet
+    /// pool and collision grid so their slots and cells are reused.
+    pub fn reset(&mut self, settings: &Settings, spawn: Vec2) {
+        let mut bullets = std::mem::replace(&mut self.bullets, BulletPool::new());
+        bullets.clear();
+        let mut collision_grid = std::mem::replace(
+            &mut self.collision_grid,
+            CollisionGrid::new(COLLISION_CELL_SIZE),
+        );
+        collision_grid.clear();
+        *self = Self {
+            bullets,
+            collision_grid,
+            ..Self::new(settings, spawn)
+        };
+    }
+
+    /// Lets the player's bullets hit the enemies they overlap, rebuilding
+    /// the collision grid first. Returns the indices of the enemies
+    /// destroyed, and how many hits enemies survived.
+    pub fn hit_enemies(&mut self) -> (Vec<usize>, u32) {
+        self.collision_grid.clear();
+        for (index, square) in self.squares.iter().enumerate() {
+            self.collision_grid.insert(index, square.rect());
+        }
+        let mut destroyed = Vec::new();
+        let mut wounded = 0;
+        for bullet in self.bullets.iter_mut() {
+            for index in self.collision_grid.query(bullet.rect()) {
+                let square = &mut self.squares[index];
+                if !square.collided
+                    && !bullet.pierced.contains(&square.id)
+                    && bullet.collides_with(square)
+                {
+                    // A piercing bullet only counts as a hit the first time
+                    if bullet.pierced.is_empty() {
+                        self.run_stats.shots_hit += 1;
+                    }
+                    if bullet.pierce_count > 0 {
+                        bullet.pierce_count -= 1;
+                        bullet.pierced.push(square.id);
+                    } else {
+                        bullet.collided = true;
+                    }
+                    square.hp = square.hp.saturating_sub(bullet.damage);
+                    if square.hp > 0 {
+                        wounded += 1;
+                    } else {
+                        square.collided = true;
+                        destroyed.push(index);
+                    }
+                    // A spent bullet mustn't go on to hit anything else it
+                    // overlaps this frame
+                    if bullet.collided {
+                        break;
+                    }
+                }
+            }
+        }
+        (destroyed, wounded)
+    }
+}
+
+/// Everything the screens share: the run in progress, the scores and the
+/// game's settings, sounds and graphics.
+pub struct GameContext {
+    pub settings: Settings,
+    pub audio: AudioManager,
+    pub assets: Assets,
+    pub sprites: Sprites,
+    pub post_process: PostProcess,
+    pub run: Run,
+    pub explosions: Explosions,
+    pub high_score: u32,
+    pub previous_high_score: u32,
+    pub leaderboards: Leaderboards,
+    pub lifetime_stats: LifetimeStats,
+    pub achievements: AchievementTracker,
+    /// Messages shown in the corner of the screen, oldest first
+    pub toasts: Vec<Toast>,
+    /// Source of every random choice that affects the gameplay, kept apart
+    /// from the one used for effects so a seeded run always plays out the same
+    pub rng: rand::RandGenerator,
+    /// Seed of the current run, if it was picked in the settings rather
+    /// than at random
+    pub seed: Option<u64>,
+    /// The run being recorded, if recording is turned on in the settings
+    pub recording: Option<Replay>,
+    /// The replay being watched, which takes the place of the player's input
+    pub playback: Option<Playback>,
+    /// Controllers, read next to the keyboard
+    pub gamepads: Gamepads,
+    /// Whether hitboxes and frame statistics are drawn over the playing field
+    pub debug: bool,
+    /// Window size as of the last frame, to notice when it is resized
+    pub screen_size: Vec2,
+    /// Seconds until the window size is saved, while it has changed since
+    /// the last save
+    pub window_size_save_timer: Option<f32>,
+}
+
+impl GameContext {
+    pub async fn new() -> Self {
+        let settings = Settings::load();
+        let mut audio = AudioManager::load(settings.audio_enabled).await;
+        audio.set_volumes(settings.music_volume, settings.sfx_volume);
+        let leaderboards = Leaderboards::load();
+        let high_score = leaderboards
+            .board(settings.game_mode, settings.difficulty)
+            .high_score;
+        Self {
+            run: Run::new(&settings, screen_center()),
+            settings,
+            audio,
+            assets: Assets::load().await,
+            sprites: Sprites::new(),
+            post_process: PostProcess::new(),
+            explosions: Explosions::new(),
+            high_score,
+            previous_high_score: high_score,
+            leaderboards,
+            lifetime_stats: LifetimeStats::load(),
+            achievements: AchievementTracker::load(),
+            toasts: Vec::new(),
+            rng: rand::RandGenerator::new(),
+            seed: None,
+            recording: None,
+            playback: None,
+            gamepads: Gamepads::new(),
+            debug: false,
+            screen_size: vec2(screen_width(), screen_height()),
+            window_size_save_timer: None,
+        }
+    }
+
+    /// Scales everything on the playing field along with the window when it
+    /// is resized, so nothing is stranded outside a window that shrank. The
+    /// new size is saved once the window has kept it for a moment.
+    pub fn handle_resize(&mut self) {
+        if let Some(timer) = &mut self.window_size_save_timer {
+            *timer -= get_frame_time();
+            if *timer <= 0.0 {
+                self.window_size_save_timer = None;
+                self.settings.save().ok();
+            }
+        }
+        let size = vec2(screen_width(), screen_height());
+        // A minimized window can report a size of zero
+        if size == self.screen_size || size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+        let scale = size / self.screen_size;
+        self.screen_size = size;
+        if !self.settings.fullscreen {
+            self.settings.window_width = size.x.round() as u32;
+            self.settings.window_height = size.y.round() as u32;
+            self.window_size_save_timer = Some(WINDOW_SIZE_SAVE_DELAY);
+        }
+
+        for square in &mut self.run.squares {
+            square.x *= scale.x;
+            square.y *= scale.y;
+        }
+        for shape in self
+            .run
+            .bullets
+            .iter_mut()
+            .chain(self.run.enemy_bullets.iter_mut())
+            .chain(self.run.coins.iter_mut())
+        {
+            shape.x *= scale.x;
+            shape.y *= scale.y;
+        }
+        for power_up in &mut self.run.power_ups {
+            power_up.x *= scale.x;
+            power_up.y *= scale.y;
+        }
+        for text in &mut self.run.floating_texts {
+            text.x *= scale.x;
+            text.y *= scale.y;
+        }
+        if let Some(boss) = &mut self.run.boss {
+            boss.x = (boss.x * scale.x)
+                .min(size.x - boss.size / 2.0)
+                .max(boss.size / 2.0);
+        }
+        self.run.circle.x *= scale.x;
+        self.run.circle.y *= scale.y;
+        self.run.circle.clamp_to_screen();
+    }
+
+    /// How much of the time to chain the next kill is left, from 1.0 right
+    /// after a kill down to 0.0 when the combo breaks.
+    pub fn combo_fraction(&self) -> f32 {
+        (self.run.combo_timer / COMBO_WINDOW).clamp(0.0, 1.0)
+    }
+
+    /// Shows a message in the corner of the screen for a moment.
+    pub fn push_toast(&mut self, text: String, color: Color) {
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+        self.toasts.push(Toast::new(text, color));
+    }
+
+    /// Ages the toasts and removes the ones that have expired.
+    pub fn update_toasts(&mut self, dt: f32) {
+        for toast in &mut self.toasts {
+            toast.update(dt);
+        }
+        self.toasts.retain(|toast| !toast.is_expired());
+    }
+
+    /// The colors of the chosen theme.
+    pub fn palette(&self) -> &'static Palette {
+        self.settings.theme.palette()
+    }
+
+    /// The leaderboard of the mode and difficulty of the current run.
+    pub fn leaderboard(&self) -> &Leaderboard {
+        self.leaderboards.board(self.run.mode, self.run.difficulty)
+    }
+
+    /// Looks up a piece of text in the chosen language.
+    pub fn tr(&self, text: Text) -> &'static str {
+        self.settings.language.tr(text)
+    }
+
+    /// Clears the playing field and everything else left over from the last
+    /// run so a new one can start. Used by every way of starting a run.
+    pub fn reset_run(&mut self) {
+        self.seed = self.settings.run_seed();
+        let seed = self.seed.unwrap_or(miniquad::date::now() as u64);
+        self.rng.srand(seed);
+        self.recording = self.settings.record_replay.then(|| {
+            Replay::new(
+                seed,
+                self.settings.difficulty,
+                self.settings.game_mode,
+                self.settings.inertia,
+            )
+        });
+        self.playback = None;
+        self.run.reset(&self.settings, screen_center());
+        self.explosions.clear();
+        self.sprites.ship.set_animation(0);
+        self.high_score = self.leaderboard().high_score;
+        self.previous_high_score = self.high_score;
+        self.audio.start_music();
+    }
+
+    /// Saves the score of the current run if it beat the high score it
+    /// started with, so a record isn't lost when a run ends or is abandoned,
+    /// along with the replay of the run if it was being recorded. The run is
+    /// also added to the lifetime stats. Practice runs only keep the replay.
+    pub fn end_run(&mut self) {
+        if self.playback.is_some() {
+            return;
+        }
+        // A practice run can still be recorded, but counts for nothing else
+        if self.run.mode.is_ranked() {
+            // Before the run is added to the lifetime stats, which the
+            // achievements would otherwise count twice
+            self.check_achievements();
+            self.lifetime_stats.add_run(&self.run.run_stats);
+            self.lifetime_stats.save().ok();
+            if self.run.score > self.previous_high_score {
+                self.leaderboards
+                    .board_mut(self.run.mode, self.run.difficulty)
+                    .high_score = self.run.score;
+                self.leaderboards.save().ok();
+            }
+        }
+        if let Some(recording) = self.recording.take() {
+            recording.save().ok();
+        }
+    }
+
+    /// Unlocks the achievements the run has earned so far, with a toast for
+    /// each new one. A watched replay or a practice run doesn't earn anything.
+    pub fn check_achievements(&mut self) {
+        if self.playback.is_some() || !self.run.mode.is_ranked() {
+            return;
+        }
+        let unlocked = self.achievements.check(
+            &self.run.run_stats,
+            &self.lifetime_stats,
+            self.previous_high_score,
+            self.run.score,
+        );
+        for achievement in unlocked {
+            let text = format!(
+                "{}: {}",
+                self.tr(Text::AchievementUnlocked),
+                self.tr(achievement.label())
+            );
+            self.push_toast(text, self.palette().reward);
+        }
+    }
+
+    /// Starts watching a recorded run from the beginning.
+    pub fn start_playback(&mut self, replay: Replay) {
+        self.reset_run();
+        self.seed = Some(replay.seed);
+        self.rng.srand(replay.seed);
+        self.run.difficulty = replay.difficulty;
+        self.run.next_extra_life_score = self.run.difficulty.extra_life_interval();
+        self.run.mode = replay.mode;
+        self.run.inertia = replay.inertia;
+        self.high_score = self.leaderboard().high_score;
+        self.previous_high_score = self.high_score;
+        self.recording = None;
+        self.playback = Some(Playback::new(replay));
+    }
+
+    pub fn toggle_audio(&mut self) {
+        self.settings.audio_enabled = !self.settings.audio_enabled;
+        self.settings.save().ok();
+        self.audio.set_enabled(self.settings.audio_enabled);
+    }
+
+    /// Switches between fullscreen and a window. The new screen size is
+    /// picked up by `handle_resize` once the window has changed.
+    pub fn toggle_fullscreen(&mut self) {
+        self.settings.fullscreen = !self.settings.fullscreen;
+        self.settings.save().ok();
+        set_fullscreen(self.settings.fullscreen);
+    }
+}
+
+/// One of the game's screens, which handles input and drawing while its
+/// state is active.
+pub trait Screen {
+    /// Advances the screen by one frame, returning the state to switch to if
+    /// it should be left.
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState>;
+
+    /// Draws the screen. The context is mutable because drawing an explosion
+    /// also advances its particles.
+    fn draw(&self, ctx: &mut GameContext);
+}
+
+impl GameState {
+    /// Creates a fresh screen for this state.
+    pub fn screen(self) -> Box<dyn Screen> {
+        match self {
+            GameState::MainMenu => Box::new(MainMenuScreen),
+            GameState::Countdown => Box::new(CountdownScreen {
+                countdown_timer: COUNTDOWN_STEP * 4.0,
+            }),
+            GameState::Playing => Box::new(PlayingScreen),
+            GameState::Dying => Box::new(DyingScreen { timer: DEATH_TIME }),
+            GameState::Paused => Box::new(PausedScreen { selection: 0 }),
+            GameState::GameOver => Box::new(GameOverScreen),
+            GameState::EnterName => Box::new(EnterNameScreen {
+                name: String::new(),
+            }),
+            GameState::Leaderboard => Box::new(LeaderboardScreen { tab: None }),
+            GameState::Stats => Box::new(StatsScreen),
+            GameState::Settings => Box::new(SettingsScreen { selection: 0 }),
+            GameState::KeyBindings => Box::new(KeyBindingsScreen {
+                selection: 0,
+                capturing: false,
+                notice: None,
+            }),
+        }
+    }
+}
+
+struct MainMenuScreen;
+
+impl MainMenuScreen {
+    const BUTTONS: [Text; 4] = [Text::Start, Text::Stats, Text::Settings, Text::Quit];
+
+    fn buttons() -> Vec<Rect> {
+        button_row(
+            Self::BUTTONS.len(),
+            screen_height() / 2.0 + 170.0 * ui_scale(),
+        )
+    }
+
+    /// Switches to the next mode when clicked.
+    fn mode_button() -> Rect {
+        let scale = ui_scale();
+        Rect::new(
+            screen_width() / 2.0 - 120.0 * scale,
+            screen_height() / 2.0 - 100.0 * scale,
+            240.0 * scale,
+            40.0 * scale,
+        )
+    }
+}
+
+impl Screen for MainMenuScreen {
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState> {
+        let buttons = Self::buttons();
+        if is_key_pressed(KeyCode::Escape)
+            || ctx.gamepads.is_pressed(Button::Select)
+            || button_clicked(buttons[3])
+        {
+            std::process::exit(0);
+        }
+        if is_key_pressed(KeyCode::T) || button_clicked(buttons[1]) {
+            return Some(GameState::Stats);
+        }
+        if is_key_pressed(KeyCode::L) {
+            return Some(GameState::Leaderboard);
+        }
+        if is_key_pressed(KeyCode::V) {
+            if let Some(replay) = Replay::load() {
+                ctx.start_playback(replay);
+                return Some(GameState::Countdown);
+            }
+        }
+        if is_key_pressed(KeyCode::S) || button_clicked(buttons[2]) {
+            return Some(GameState::Settings);
+        }
+        let direction =
+            if is_key_pressed(KeyCode::Left) || ctx.gamepads.is_pressed(Button::DPadLeft) {
+                -1
+            } else if is_key_pressed(KeyCode::Right)
+                || ctx.gamepads.is_pressed(Button::DPadRight)
+                || button_clicked(Self::mode_button())
+            {
+                1
+            } else {
+                0
+            };
+        if direction != 0 {
+            ctx.settings.game_mode = cycle(&GameMode::ALL, ctx.settings.game_mode, direction);
+            ctx.settings.save().ok();
+        }
+        if is_key_pressed(KeyCode::Space)
+            || ctx.gamepads.is_pressed(Button::South)
+            || ctx.gamepads.is_pressed(Button::Start)
+            || button_clicked(buttons[0])
+        {
+            ctx.reset_run();
+            return Some(GameState::Countdown);
+        }
+        None
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        let palette = ctx.palette();
+        draw_centered_text(
+            ctx.tr(Text::PressSpace),
+            screen_height() / 2.0,
+            50.0,
+            palette.text,
+        );
+        let bindings = &ctx.settings.key_bindings;
+        let fire = match ctx.settings.control_scheme {
+            ControlScheme::Keyboard => key_name(bindings.fire),
+            ControlScheme::Mouse => ctx.tr(Text::Mouse).to_string(),
+        };
+        let controls = format!(
+            "{}/{}/{}/{}: {}   {}: {}   {}: {}   {}: {}",
+            key_name(bindings.move_up),
+            key_name(bindings.move_left),
+            key_name(bindings.move_down),
+            key_name(bindings.move_right),
+            ctx.tr(Text::MoveControl),
+            fire,
+            ctx.tr(Text::FireControl),
+            key_name(bindings.pause),
+            ctx.tr(Text::PauseControl),
+            key_name(bindings.bomb),
+            ctx.tr(Text::BombControl),
+        );
+        draw_centered_text(
+            controls.as_str(),
+            screen_height() / 2.0 + 35.0 * ui_scale(),
+            20.0,
+            palette.faint_text,
+        );
+        draw_centered_text(
+            ctx.tr(Text::LeaderboardHint),
+            screen_height() / 2.0 + 65.0 * ui_scale(),
+            25.0,
+            palette.dim_text,
+        );
+        let text = if ctx.settings.audio_enabled {
+            Text::MuteHint
+        } else {
+            Text::UnmuteHint
+        };
+        draw_centered_text(
+            ctx.tr(text),
+            screen_height() / 2.0 + 92.0 * ui_scale(),
+            25.0,
+            palette.dim_text,
+        );
+        draw_centered_text(
+            ctx.tr(Text::SettingsHint),
+            screen_height() / 2.0 + 119.0 * ui_scale(),
+            25.0,
+            palette.dim_text,
+        );
+        draw_centered_text(
+            ctx.tr(Text::ReplayHint),
+            screen_height() / 2.0 + 146.0 * ui_scale(),
+            25.0,
+            palette.dim_text,
+        );
+        for (rect, label) in Self::buttons().into_iter().zip(Self::BUTTONS) {
+            draw_button(rect, ctx.tr(label), false, palette);
+        }
+        let mode = format!(
+            "< {}: {} >",
+            ctx.tr(Text::GameMode),
+            ctx.tr(ctx.settings.game_mode.label())
+        );
+        draw_button(Self::mode_button(), mode.as_str(), false, palette);
+    }
+}
+
+/// Shows "3, 2, 1, Go!" over the frozen playing field before a run starts.
+struct CountdownScreen {
+    /// Seconds left until the run starts
+    countdown_timer: f32,
+}
+
+impl Screen for CountdownScreen {
+    fn update(&mut self, _ctx: &mut GameContext) -> Option<GameState> {
+        self.countdown_timer -= get_frame_time().min(MAX_FRAME_TIME);
+        if self.countdown_timer <= 0.0 {
+            return Some(GameState::Playing);
+        }
+        None
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        draw_world(ctx);
+        draw_hud(ctx);
+        let step = (self.countdown_timer / COUNTDOWN_STEP).ceil() as u32;
+        let text = match step {
+            0 | 1 => ctx.tr(Text::Go).to_string(),
+            _ => (step - 1).to_string(),
+        };
+        draw_centered_text(
+            text.as_str(),
+            screen_height() / 2.0,
+            120.0,
+            ctx.palette().text,
+        );
+    }
+}
+
+/// Awards the score and drops for a destroyed enemy and sets off its
+/// explosion. Kills set off by the blasts of others are worth more the
+/// further down the chain they are. Destroying a friendly costs score and
+/// breaks the combo instead.
+fn destroy_enemy(ctx: &mut GameContext, index: usize, chain: u32) {
+    let square = &ctx.run.squares[index];
+    if square.kind == EnemyKind::Friendly {
+        let penalty = ctx
+            .run
+            .difficulty
+            .scale_score(square.score() * FRIENDLY_PENALTY_MULTIPLIER);
+        ctx.run.score = ctx.run.score.saturating_sub(penalty);
+        ctx.run.combo = 1;
+        ctx.run
+            .floating_texts
+            .push(FloatingText::new(square.x, square.y, -(penalty as i64)));
+        ctx.explosions.push(enemy_explosion(
+            square,
+            &ctx.assets.explosions_texture,
+            ctx.palette(),
+            ctx.settings.reduce_motion,
+        ));
+        ctx.audio.play_hit();
+        return;
+    }
+    let gained = ctx
+        .run
+        .difficulty
+        .scale_score(square.score() * ctx.run.combo * (chain + 1));
+    ctx.run.score += gained;
+    ctx.run
+        .floating_texts
+        .push(FloatingText::new(square.x, square.y, gained as i64));
+    ctx.run.run_stats.enemies_destroyed += 1;
+    ctx.high_score = ctx.high_score.max(ctx.run.score);
+    ctx.run.combo += 1;
+    ctx.run.combo_timer = COMBO_WINDOW;
+    ctx.run.run_stats.max_combo = ctx.run.run_stats.max_combo.max(ctx.run.combo);
+    if ctx.rng.gen_range(0, POWER_UP_DROP_ODDS) == 0 {
+        ctx.run.power_ups.push(PowerUp::new(
+            PowerUpKind::random(&ctx.rng),
+            square.x,
+            square.y,
+        ));
+    }
+    if ctx.rng.gen_range(0, COIN_DROP_ODDS) == 0 {
+        ctx.run.coins.push(Shape {
+            size: 12.0,
+            x: square.x,
+            y: square.y,
+            vx: 0.0,
+            vy: COIN_SPEED,
+            collided: false,
+            grazed: false,
+            damage: 1,
+            pierce_count: 0,
+            pierced: Vec::new(),
+        });
+    }
+    ctx.explosions.push(enemy_explosion(
+        square,
+        &ctx.assets.explosions_texture,
+        ctx.palette(),
+        ctx.settings.reduce_motion,
+    ));
+    ctx.run.screen_shake += ENEMY_EXPLOSION_SHAKE;
+    ctx.audio.play_explosion();
+    let pieces = ctx.run.squares[index].on_destroy(&mut ctx.run.next_enemy_id, &ctx.rng);
+    ctx.run.squares.extend(pieces);
+}
+
+/// Generates new squares at a fixed rate regardless of frame rate,
+/// spawning more and faster enemies the longer the run lasts.
+fn spawn_endless(ctx: &mut GameContext, delta_time: f32) {
+    let ramp = ramp_progress(ctx.run.elapsed_time);
+    let spawn_interval = ctx.run.difficulty.spawn_interval() * (1.0 - RAMP_SPAWN_REDUCTION * ramp);
+    let speed_scale = ctx.run.difficulty.enemy_speed_scale() * (1.0 + RAMP_SPEED_INCREASE * ramp);
+    // Hold back the regular enemies while a boss is on its way or on screen,
+    // or while the screen is full
+    let max_enemies = ctx.run.difficulty.max_enemies();
+    if ctx.run.boss.is_none()
+        && ctx.run.boss_warning.is_none()
+        && ctx.run.squares.len() < max_enemies
+    {
+        ctx.run.spawn_timer += delta_time;
+    }
+    while ctx.run.spawn_timer >= spawn_interval && ctx.run.squares.len() < max_enemies {
+        ctx.run.spawn_timer -= spawn_interval;
+        ctx.run.next_enemy_id += 1;
+        ctx.run.squares.push(Enemy::spawn(
+            ctx.run.next_enemy_id,
+            EnemyKind::random(&ctx.rng),
+            Movement::random(ctx.run.elapsed_time, &ctx.rng),
+            speed_scale,
+            ctx.run.spawn_columns.pick(&ctx.rng),
+            &ctx.rng,
+        ));
+    }
+}
+
+/// Lets the enemies of the current wave in one at a time, and once they
+/// are all gone awards the bonus and takes a break before the next wave.
+fn spawn_waves(ctx: &mut GameContext, delta_time: f32) {
+    if ctx.run.wave_break > 0.0 {
+        ctx.run.wave_break -= delta_time;
+        if ctx.run.wave_break <= 0.0 {
+            ctx.run.wave_break = 0.0;
+            // Reversed so the next one to spawn can be popped off the end
+            ctx.run.wave_enemies = wave_enemies(ctx.run.wave, &ctx.rng);
+            ctx.run.wave_enemies.reverse();
+            ctx.run.spawn_timer = 0.0;
+        }
+        return;
+    }
+    if ctx.run.wave_enemies.is_empty()
+        && ctx.run.squares.is_empty()
+        && ctx.run.boss.is_none()
+        && ctx.run.boss_warning.is_none()
+    {
+        let bonus = ctx.run.difficulty.scale_score(WAVE_BONUS * ctx.run.wave);
+        ctx.run.score += bonus;
+        ctx.high_score = ctx.high_score.max(ctx.run.score);
+        let text = format!("{} +{}", ctx.tr(Text::WaveCleared), bonus);
+        ctx.push_toast(text, ctx.palette().reward);
+        ctx.run.wave += 1;
+        ctx.run.wave_break = WAVE_BREAK_TIME;
+        return;
+    }
+    let spawn_interval = ctx.run.difficulty.spawn_interval();
+    let speed_scale = ctx.run.difficulty.enemy_speed_scale() * wave_speed_scale(ctx.run.wave);
+    let max_enemies = ctx.run.difficulty.max_enemies();
+    if ctx.run.boss.is_none()
+        && ctx.run.boss_warning.is_none()
+        && ctx.run.squares.len() < max_enemies
+    {
+        ctx.run.spawn_timer += delta_time;
+    }
+    while ctx.run.spawn_timer >= spawn_interval && ctx.run.squares.len() < max_enemies {
+        ctx.run.spawn_timer -= spawn_interval;
+        let Some((kind, movement)) = ctx.run.wave_enemies.pop() else {
+            break;
+        };
+        ctx.run.next_enemy_id += 1;
+        ctx.run.squares.push(Enemy::spawn(
+            ctx.run.next_enemy_id,
+            kind,
+            movement,
+            speed_scale,
+            ctx.run.spawn_columns.pick(&ctx.rng),
+            &ctx.rng,
+        ));
+    }
+}
+
+struct PlayingScreen;
+
+impl Screen for PlayingScreen {
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState> {
+        // Macroquad doesn't report focus changes, so a stall or a minimized
+        // window stands in for the player having switched away. Pausing
+        // before the input is read keeps the stalled frame out of replays.
+        let minimized = screen_width() <= 0.0 || screen_height() <= 0.0;
+        if get_frame_time() > FOCUS_LOSS_FRAME_TIME || minimized {
+            return Some(GameState::Paused);
+        }
+        let mut next_state = None;
+        let input = match &mut ctx.playback {
+            Some(playback) => match playback.next_input() {
+                Some(input) => input,
+                None => return Some(GameState::MainMenu),
+            },
+            // Cap the frame time so a stall doesn't make shapes skip past each other
+            None => InputFrame::read(
+                get_frame_time().min(MAX_FRAME_TIME),
+                ctx.settings.control_scheme,
+                &ctx.settings.key_bindings,
+                &ctx.gamepads,
+                vec2(ctx.run.circle.x, ctx.run.circle.y),
+            ),
+        };
+        if let Some(recording) = &mut ctx.recording {
+            recording.frames.push(input);
+        }
+        let delta_time = input.delta_time;
+        ctx.run
+            .circle
+            .update(delta_time, input.movement(), ctx.run.inertia);
+        // Beat along with the pulse of the low health vignette
+        let heartbeat = (ctx.run.circle.clock / HEARTBEAT_INTERVAL) as u64;
+        if ctx.run.circle.lives == 1 && heartbeat != ctx.run.last_heartbeat {
+            ctx.audio.play_heartbeat();
+        }
+        ctx.run.last_heartbeat = heartbeat;
+        // Lean the starfield while steering sideways, and let it drift back
+        // once the player stops
+        if ctx.run.circle.direction == 0.0 {
+            let decay = DIRECTION_MODIFIER_DECAY * delta_time;
+            ctx.run.direction_modifier -= ctx.run.direction_modifier.clamp(-decay, decay);
+        } else {
+            let max = if ctx.settings.reduce_motion {
+                REDUCED_MAX_DIRECTION_MODIFIER
+            } else {
+                MAX_DIRECTION_MODIFIER
+            };
+            ctx.run.direction_modifier = (ctx.run.direction_modifier
+                + 0.05 * delta_time * ctx.run.circle.direction)
+                .clamp(-max, max);
+        }
+        // Speed up the starfield while moving and firing
+        let max_warp = if ctx.settings.reduce_motion {
+            REDUCED_MAX_WARP
+        } else {
+            1.0
+        };
+        let warp = (input.movement().length().min(1.0) * MOVE_WARP
+            + input.fire as i32 as f32 * FIRE_WARP)
+            .min(max_warp);
+        ctx.run.warp_intensity +=
+            (warp - ctx.run.warp_intensity) * (WARP_EASE * delta_time).min(1.0);
+        let time_scale = if ctx.run.circle.has_time_slow() {
+            TIME_SLOW_SCALE
+        } else {
+            1.0
+        };
+        ctx.run.time_scale +=
+            (time_scale - ctx.run.time_scale) * (TIME_SCALE_EASE * delta_time).min(1.0);
+        // The enemies' side of the world, which a time slow holds back while
+        // the ship and its bullets keep full speed
+        let world_delta_time = delta_time * ctx.run.time_scale;
+        if ctx.run.circle.direction > 0.0 {
+            ctx.sprites.ship.set_animation(2);
+        } else if ctx.run.circle.direction < 0.0 {
+            ctx.sprites.ship.set_animation(1);
+        } else {
+            ctx.sprites.ship.set_animation(0);
+        }
+        let aim = input.aim();
+        let muzzle = vec2(ctx.run.circle.x, ctx.run.circle.y) + aim * 24.0;
+        let trigger = ctx.run.circle.pull_trigger(input.fire, delta_time);
+        match trigger {
+            Trigger::Idle => {}
+            Trigger::Shot => {
+                let angles: &[f32] = match ctx.run.circle.weapon {
+                    Weapon::Single => &[0.0],
+                    Weapon::Spread => &[-SPREAD_ANGLE, 0.0, SPREAD_ANGLE],
+                };
+                let bullet_speed = ctx.run.circle.speed * 2.0;
+                ctx.run.run_stats.shots_fired += angles.len() as u32;
+                for angle in angles {
+                    let velocity = Vec2::from_angle(*angle).rotate(aim) * bullet_speed;
+                    ctx.run.bullets.spawn(Shape {
+                        x: muzzle.x,
+                        y: muzzle.y,
+                        vx: velocity.x,
+                        vy: velocity.y,
+                        size: 32.0,
+                        collided: false,
+                        grazed: false,
+                        damage: 1,
+                        pierce_count: 0,
+                        pierced: Vec::new(),
+                    });
+                }
+            }
+            Trigger::ChargedShot(charge) => {
+                ctx.run.run_stats.shots_fired += 1;
+                let velocity = aim * ctx.run.circle.speed * (2.5 + charge);
+                ctx.run.bullets.spawn(Shape {
+                    x: muzzle.x,
+                    y: muzzle.y,
+                    vx: velocity.x,
+                    vy: velocity.y,
+                    size: 32.0 * (1.5 + charge),
+                    collided: false,
+                    grazed: false,
+                    damage: 2 + (charge * CHARGED_EXTRA_DAMAGE as f32) as u32,
+                    pierce_count: 1 + (charge * CHARGED_EXTRA_PIERCE as f32) as u32,
+                    pierced: Vec::new(),
+                });
+            }
+        }
+        if !matches!(trigger, Trigger::Idle) {
+            ctx.explosions.push((
+                Emitter::new(EmitterConfig {
+                    initial_direction: aim,
+                    ..muzzle_flash(ctx.settings.reduce_motion)
+                }),
+                muzzle,
+            ));
+            ctx.audio.play_shoot();
+        }
+        if is_key_pressed(ctx.settings.key_bindings.pause) || ctx.gamepads.is_pressed(Button::Start)
+        {
+            next_state = Some(GameState::Paused);
+        }
+        if is_key_pressed(ctx.settings.key_bindings.restart) {
+            ctx.end_run();
+            ctx.reset_run();
+            return Some(GameState::Countdown);
+        }
+        if input.bomb && ctx.run.circle.bombs > 0 {
+            ctx.run.circle.bombs -= 1;
+            let palette = ctx.palette();
+            // Bombs spare the friendlies, and leave nothing of splitters behind
+            for square in ctx
+                .run
+                .squares
+                .iter_mut()
+                .filter(|square| !square.collided && square.kind != EnemyKind::Friendly)
+            {
+                square.collided = true;
+                let gained = ctx.run.difficulty.scale_score(square.score() / 2);
+                ctx.run.score += gained;
+                ctx.run.run_stats.enemies_destroyed += 1;
+                ctx.run
+                    .floating_texts
+                    .push(FloatingText::new(square.x, square.y, gained as i64));
+                ctx.explosions.push(enemy_explosion(
+                    square,
+                    &ctx.assets.explosions_texture,
+                    palette,
+                    ctx.settings.reduce_motion,
+                ));
+            }
+            ctx.high_score = ctx.high_score.max(ctx.run.score);
+            ctx.run.enemy_bullets.clear();
+            ctx.run.screen_shake += BOMB_SHAKE;
+            ctx.audio.play_explosion();
+        }
+
+        ctx.run.elapsed_time += delta_time;
+        if ctx.run.mode == GameMode::TimeAttack {
+            ctx.run.time_left = (ctx.run.time_left - delta_time).max(0.0);
+        }
+        ctx.run.run_stats.time_survived += delta_time;
+        ctx.run.combo_timer -= delta_time;
+        ctx.run.screen_shake = (ctx.run.screen_shake
+            * (1.0 - SCREEN_SHAKE_DECAY * delta_time).max(0.0))
+        .min(MAX_SCREEN_SHAKE);
+        ctx.run.graze_pulse = (ctx.run.graze_pulse - delta_time / GRAZE_PULSE_TIME).max(0.0);
+        if ctx.run.combo_timer <= 0.0 {
+            ctx.run.combo = 1;
+        }
+
+        match ctx.run.mode {
+            GameMode::Endless | GameMode::TimeAttack | GameMode::Practice => {
+                spawn_endless(ctx, world_delta_time)
+            }
+            GameMode::Waves => spawn_waves(ctx, world_delta_time),
+        }
+
+        // Movement
+        for square in &mut ctx.run.squares {
+            square.update(world_delta_time, vec2(ctx.run.circle.x, ctx.run.circle.y));
+        }
+        for shape in ctx.run.bullets.iter_mut().chain(ctx.run.coins.iter_mut()) {
+            shape.update(delta_time);
+        }
+        for bullet in &mut ctx.run.enemy_bullets {
+            bullet.update(world_delta_time);
+        }
+        for power_up in &mut ctx.run.power_ups {
+            power_up.update(delta_time);
+        }
+        for text in &mut ctx.run.floating_texts {
+            text.update(delta_time);
+        }
+        for coin in &mut ctx.run.coins {
+            // Steer toward the player while the magnet is live, and
+            // fall straight down again once it runs out
+            let desired = if ctx.run.circle.has_magnet() {
+                (vec2(ctx.run.circle.x, ctx.run.circle.y) - vec2(coin.x, coin.y))
+                    .normalize_or_zero()
+                    * MAGNET_MAX_SPEED
+            } else {
+                vec2(0.0, COIN_SPEED)
+            };
+            let velocity = vec2(coin.vx, coin.vy);
+            let velocity =
+                velocity + (desired - velocity).clamp_length_max(MAGNET_PULL * delta_time);
+            coin.vx = velocity.x;
+            coin.vy = velocity.y;
+        }
+        if let Some(boss) = &mut ctx.run.boss {
+            boss.update(world_delta_time);
+        }
+
+        // Let armed enemies fire at the player
+        for square in &mut ctx.run.squares {
+            if let Some(bullet) =
+                square.shoot(world_delta_time, vec2(ctx.run.circle.x, ctx.run.circle.y))
+            {
+                ctx.run.enemy_bullets.push(bullet);
+            }
+        }
+
+        ctx.sprites.update();
+
+        // Remove shapes outside of screen
+        ctx.run.squares.retain(|square| {
+            square.y < screen_height() + square.size
+                && square.y > -square.size * 2.0
+                && square.x > -square.size
+                && square.x < screen_width() + square.size
+        });
+        ctx.run.bullets.retain(|bullet| {
+            bullet.y > 0.0 - bullet.size / 2.0
+                && bullet.y < screen_height() + bullet.size / 2.0
+                && bullet.x > -bullet.size / 2.0
+                && bullet.x < screen_width() + bullet.size / 2.0
+        });
+        ctx.run.enemy_bullets.retain(|bullet| {
+            bullet.y < screen_height() + bullet.size
+                && bullet.x > -bullet.size
+                && bullet.x < screen_width() + bullet.size
+        });
+
+        ctx.run
+            .power_ups
+            .retain(|power_up| power_up.y < screen_height() + power_up.size);
+        ctx.run.coins.retain(|coin| {
+            coin.y < screen_height() + coin.size
+                && coin.x > -coin.size
+                && coin.x < screen_width() + coin.size
+        });
+
+        // Remove collided shapes
+        ctx.run.squares.retain(|square| !square.collided);
+        ctx.run.bullets.retain(|bullet| !bullet.collided);
+        ctx.run.power_ups.retain(|power_up| !power_up.collided);
+        ctx.run.coins.retain(|coin| !coin.collided);
+        ctx.run.floating_texts.retain(|text| !text.is_expired());
+
+        // Remove old explosions
+        ctx.explosions.remove_finished();
+
+        // Check for collisions, finding where the ship was hit from
+        let hit = if ctx.run.circle.is_invincible() {
+            None
+        } else {
+            ctx.run
+                .squares
+                .iter()
+                .filter(|square| FRIENDLIES_HURT || square.kind != EnemyKind::Friendly)
+                .find(|square| ctx.run.circle.collides_with(*square))
+                .map(|square| vec2(square.x, square.y))
+                .or_else(|| {
+                    ctx.run
+                        .enemy_bullets
+                        .iter()
+                        .find(|bullet| ctx.run.circle.collides_with(*bullet))
+                        .map(|bullet| vec2(bullet.x, bullet.y))
+                })
+                .or_else(|| {
+                    ctx.run
+                        .boss
+                        .as_ref()
+                        .filter(|boss| ctx.run.circle.collides_with(*boss))
+                        .map(|boss| vec2(boss.x, boss.y))
+                })
+        };
+        if let Some(hit) = hit {
+            ctx.audio.play_hit();
+            if ctx.run.mode == GameMode::Practice {
+                ctx.run.circle.knock_back(hit);
+            } else {
+                ctx.run.circle.lives -= 1;
+                if ctx.run.circle.lives == 0 {
+                    ctx.explosions.push((
+                        Emitter::new(EmitterConfig {
+                            texture: Some(ctx.assets.explosions_texture.clone()),
+                            ..player_explosion(ctx.settings.reduce_motion)
+                        }),
+                        vec2(ctx.run.circle.x, ctx.run.circle.y),
+                    ));
+                    ctx.audio.play_explosion();
+                    // Nothing else in the frame gets to score, so the score
+                    // saved before the death plays out is the final one
+                    ctx.end_run();
+                    return Some(GameState::Dying);
+                } else {
+                    ctx.run.squares.retain(|square| {
+                        vec2(square.x - ctx.run.circle.x, square.y - ctx.run.circle.y).length()
+                            > RESPAWN_CLEAR_RADIUS
+                    });
+                    ctx.run.enemy_bullets.clear();
+                    ctx.run.circle.respawn();
+                }
+            }
+        }
+        if ctx.run.mode == GameMode::TimeAttack && ctx.run.time_left == 0.0 {
+            // Stop here so the score saved is the one shown on game over
+            ctx.end_run();
+            return Some(GameState::GameOver);
+        }
+        // Reward near misses, once for every enemy and bullet that passes by
+        if !ctx.run.circle.is_invincible() {
+            let graze_rect = ctx.run.circle.graze_rect();
+            let mut grazes = 0;
+            for square in ctx.run.squares.iter_mut() {
+                if !square.grazed
+                    && !square.collided
+                    && square.kind != EnemyKind::Friendly
+                    && square.rect().overlaps(&graze_rect)
+                {
+                    square.grazed = true;
+                    grazes += 1;
+                }
+            }
+            for bullet in ctx.run.enemy_bullets.iter_mut() {
+                if !bullet.grazed && bullet.rect().overlaps(&graze_rect) {
+                    bullet.grazed = true;
+                    grazes += 1;
+                }
+            }
+            if grazes > 0 {
+                ctx.run.score += ctx.run.difficulty.scale_score(GRAZE_SCORE * grazes);
+                ctx.high_score = ctx.high_score.max(ctx.run.score);
+                ctx.run.graze_pulse = 1.0;
+            }
+        }
+        let mut picked_up = Vec::new();
+        for power_up in ctx.run.power_ups.iter_mut() {
+            if ctx.run.circle.collides_with(power_up) {
+                power_up.collided = true;
+                picked_up.push(power_up.kind);
+            }
+        }
+        for kind in picked_up {
+            ctx.run.circle.apply_power_up(kind);
+            ctx.push_toast(ctx.tr(kind.name()).to_string(), kind.color(ctx.palette()));
+        }
+        for coin in ctx.run.coins.iter_mut() {
+            if ctx.run.circle.collides_with(coin) {
+                coin.collided = true;
+                ctx.run.score += ctx.run.difficulty.scale_score(COIN_SCORE * ctx.run.combo);
+                ctx.high_score = ctx.high_score.max(ctx.run.score);
+                ctx.run.combo += 1;
+                ctx.run.combo_timer = COMBO_WINDOW;
+            }
+        }
+        let (destroyed, wounded) = ctx.run.hit_enemies();
+        for _ in 0..wounded {
+            ctx.audio.play_hit();
+        }
+        // Enemies destroyed this frame, with how many blasts led up to each kill
+        let mut kills: Vec<(usize, u32)> = destroyed.into_iter().map(|index| (index, 0)).collect();
+        // Every kill sends out a blast that damages the enemies around it,
+        // which can set off further kills up to `MAX_CHAIN_LENGTH` blasts deep
+        let mut next_kill = 0;
+        while let Some(&(index, chain)) = kills.get(next_kill) {
+            next_kill += 1;
+            destroy_enemy(ctx, index, chain);
+            // Friendlies go out with a fizzle rather than a blast
+            if chain >= MAX_CHAIN_LENGTH || ctx.run.squares[index].kind == EnemyKind::Friendly {
+                continue;
+            }
+            let square = &ctx.run.squares[index];
+            let center = vec2(square.x, square.y);
+            let radius = square.size * BLAST_RADIUS_PER_SIZE;
+            let blast = Rect::new(
+                center.x - radius,
+                center.y - radius,
+                radius * 2.0,
+                radius * 2.0,
+            );
+            for other in ctx.run.collision_grid.query(blast) {
+                let square = &mut ctx.run.squares[other];
+                // Blasts spare the friendlies, like bombs do
+                if square.collided
+                    || square.kind == EnemyKind::Friendly
+                    || vec2(square.x, square.y).distance(center) > radius + square.size / 2.0
+                {
+                    continue;
+                }
+                square.hp -= 1;
+                if square.hp == 0 {
+                    square.collided = true;
+                    kills.push((other, chain + 1));
+                }
+            }
+        }
+        if let Some(current_boss) = &mut ctx.run.boss {
+            for bullet in ctx.run.bullets.iter_mut() {
+                if !bullet.collided && current_boss.hp > 0 && bullet.collides_with(current_boss) {
+                    if bullet.pierced.is_empty() {
+                        ctx.run.run_stats.shots_hit += 1;
+                    }
+                    bullet.collided = true;
+                    current_boss.hp = current_boss.hp.saturating_sub(bullet.damage);
+                    ctx.audio.play_hit();
+                }
+            }
+            if current_boss.hp == 0 {
+                ctx.run.score += ctx.run.difficulty.scale_score(BOSS_SCORE);
+                ctx.run.run_stats.enemies_destroyed += 1;
+                ctx.high_score = ctx.high_score.max(ctx.run.score);
+                ctx.run.next_boss_score =
+                    (ctx.run.score / BOSS_SCORE_INTERVAL + 1) * BOSS_SCORE_INTERVAL;
+                ctx.explosions.extend(boss_explosion(
+                    current_boss,
+                    &ctx.assets.explosions_texture,
+                    ctx.settings.reduce_motion,
+                ));
+                ctx.run.screen_shake += BOSS_EXPLOSION_SHAKE;
+                ctx.audio.play_explosion();
+                ctx.run.boss = None;
+            }
+        } else if let Some(warning) = &mut ctx.run.boss_warning {
+            *warning -= delta_time;
+            if *warning <= 0.0 {
+                ctx.run.boss_warning = None;
+                ctx.run.boss = Some(Boss::spawn());
+            }
+        } else if ctx.run.score >= ctx.run.next_boss_score {
+            ctx.run.boss_warning = Some(BOSS_WARNING_TIME);
+        }
+        while ctx.run.score >= ctx.run.next_extra_life_score {
+            ctx.run.next_extra_life_score += ctx.run.difficulty.extra_life_interval();
+            if ctx.run.circle.lives < MAX_LIVES {
+                ctx.run.circle.lives += 1;
+                ctx.audio.play_extra_life();
+                ctx.push_toast(ctx.tr(Text::ExtraLife).to_string(), ctx.palette().player);
+            }
+        }
+        ctx.check_achievements();
+        next_state
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        set_shake_camera(ctx);
+        draw_world(ctx);
+        draw_explosions(ctx);
+        ctx.post_process.set_camera(Vec2::ZERO);
+        draw_time_slow_tint(ctx);
+        draw_low_health_vignette(ctx);
+        draw_hud(ctx);
+        if ctx.run.mode == GameMode::Waves && ctx.run.wave_break > 0.0 {
+            draw_wave_banner(ctx);
+        }
+        if ctx.run.boss_warning.is_some() {
+            draw_boss_warning(ctx);
+        }
+    }
+}
+
+struct PausedScreen {
+    /// Highlighted option
+    selection: usize,
+}
+
+impl PausedScreen {
+    const OPTIONS: [Text; 3] = [Text::Resume, Text::Restart, Text::QuitToMenu];
+
+    fn buttons() -> Vec<Rect> {
+        button_column(
+            Self::OPTIONS.len(),
+            screen_height() / 2.0 - 20.0 * ui_scale(),
+        )
+    }
+}
+
+impl Screen for PausedScreen {
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState> {
+        if is_key_pressed(ctx.settings.key_bindings.pause) || ctx.gamepads.is_pressed(Button::Start)
+        {
+            return Some(GameState::Playing);
+        }
+        let options = Self::OPTIONS.len();
+        if is_key_pressed(KeyCode::Up) || ctx.gamepads.is_pressed(Button::DPadUp) {
+            self.selection = (self.selection + options - 1) % options;
+        }
+        if is_key_pressed(KeyCode::Down) || ctx.gamepads.is_pressed(Button::DPadDown) {
+            self.selection = (self.selection + 1) % options;
+        }
+        let mut chosen = None;
+        if is_key_pressed(KeyCode::Enter)
+            || is_key_pressed(KeyCode::Space)
+            || ctx.gamepads.is_pressed(Button::South)
+        {
+            chosen = Some(self.selection);
+        }
+        if let Some(index) = Self::buttons().into_iter().position(button_clicked) {
+            chosen = Some(index);
+        }
+        match chosen.map(|index| Self::OPTIONS[index]) {
+            Some(Text::Resume) => Some(GameState::Playing),
+            Some(Text::Restart) => {
+                ctx.end_run();
+                ctx.reset_run();
+                Some(GameState::Countdown)
+            }
+            Some(Text::QuitToMenu) => {
+                ctx.end_run();
+                Some(GameState::MainMenu)
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        let palette = ctx.palette();
+        // Explosions are left out so they stand still until the game resumes
+        draw_world(ctx);
+        draw_time_slow_tint(ctx);
+        draw_hud(ctx);
+        draw_dim_overlay(PAUSE_DIM);
+        draw_centered_text(
+            ctx.tr(Text::Paused),
+            screen_height() / 2.0 - 60.0 * ui_scale(),
+            50.0,
+            palette.text,
+        );
+        for (i, (rect, label)) in Self::buttons().into_iter().zip(Self::OPTIONS).enumerate() {
+            draw_button(rect, ctx.tr(label), i == self.selection, palette);
+        }
+    }
+}
+
+/// Lets the world drift on in slow motion around the player's explosion,
+/// then moves on to the game over screen.
+struct DyingScreen {
+    /// Seconds left until the game over screen
+    timer: f32,
+}
+
+impl Screen for DyingScreen {
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState> {
+        let delta_time = get_frame_time().min(MAX_FRAME_TIME);
+        self.timer -= delta_time;
+        if self.timer <= 0.0 {
+            return Some(GameState::GameOver);
+        }
+        let world_delta_time = delta_time * DEATH_TIME_SCALE;
+        let ship = vec2(ctx.run.circle.x, ctx.run.circle.y);
+        for square in &mut ctx.run.squares {
+            square.update(world_delta_time, ship);
+        }
+        for shape in ctx
+            .run
+            .bullets
+            .iter_mut()
+            .chain(ctx.run.enemy_bullets.iter_mut())
+            .chain(ctx.run.coins.iter_mut())
+        {
+            shape.update(world_delta_time);
+        }
+        if let Some(boss) = &mut ctx.run.boss {
+            boss.update(world_delta_time);
+        }
+        for text in &mut ctx.run.floating_texts {
+            text.update(world_delta_time);
+        }
+        ctx.explosions.remove_finished();
+        None
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        set_shake_camera(ctx);
+        draw_world(ctx);
+        draw_explosions(ctx);
+        ctx.post_process.set_camera(Vec2::ZERO);
+        draw_hud(ctx);
+    }
+}
+
+struct GameOverScreen;
+
+impl GameOverScreen {
+    const BUTTONS: [Text; 2] = [Text::PlayAgain, Text::Menu];
+
+    fn buttons() -> Vec<Rect> {
+        button_row(
+            Self::BUTTONS.len(),
+            screen_height() / 2.0 + 130.0 * ui_scale(),
+        )
+    }
+}
+
+impl Screen for GameOverScreen {
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState> {
+        let buttons = Self::buttons();
+        if is_key_pressed(KeyCode::Space)
+            || ctx.gamepads.is_pressed(Button::South)
+            || button_clicked(buttons[1])
+        {
+            // A watched replay has already had its chance at the leaderboard
+            if ctx.playback.is_none()
+                && ctx.run.mode.is_ranked()
+                && ctx.leaderboard().qualifies(ctx.run.score)
+            {
+                // Throw away the keys typed while playing
+                while get_char_pressed().is_some() {}
+                return Some(GameState::EnterName);
+            }
+            return Some(GameState::MainMenu);
+        }
+        if is_key_pressed(ctx.settings.key_bindings.restart) || button_clicked(buttons[0]) {
+            ctx.reset_run();
+            return Some(GameState::Countdown);
+        }
+        None
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        let palette = ctx.palette();
+        for (rect, label) in Self::buttons().into_iter().zip(Self::BUTTONS) {
+            draw_button(rect, ctx.tr(label), false, palette);
+        }
+        // Running out of time isn't a failure, so it gets its own title
+        let title = if ctx.run.mode == GameMode::TimeAttack && ctx.run.time_left == 0.0 {
+            Text::TimeUp
+        } else {
+            Text::GameOver
+        };
+        draw_centered_text(
+            ctx.tr(title),
+            screen_height() / 2.0 - 120.0 * ui_scale(),
+            50.0,
+            palette.danger,
+        );
+        let stats = &ctx.run.run_stats;
+        let seconds = stats.time_survived as u32;
+        let mut lines = vec![
+            format!("{}: {}", ctx.tr(Text::ShotsFired), stats.shots_fired),
+            format!("{}: {}", ctx.tr(Text::ShotsHit), stats.shots_hit),
+            format!("{}: {:.0}%", ctx.tr(Text::Accuracy), stats.accuracy()),
+            format!(
+                "{}: {}",
+                ctx.tr(Text::EnemiesDestroyed),
+                stats.enemies_destroyed
+            ),
+            format!(
+                "{}: {}:{:02}",
+                ctx.tr(Text::TimeSurvived),
+                seconds / 60,
+                seconds % 60
+            ),
+            format!("{}: x{}", ctx.tr(Text::MaxCombo), stats.max_combo),
+        ];
+        if ctx.run.mode == GameMode::Waves {
+            lines.insert(
+                0,
+                format!("{}: {}", ctx.tr(Text::WaveReached), ctx.run.wave),
+            );
+        }
+        for (i, line) in lines.iter().enumerate() {
+            draw_centered_text(
+                line.as_str(),
+                screen_height() / 2.0 - (80.0 - i as f32 * 20.0) * ui_scale(),
+                22.0,
+                palette.text,
+            );
+        }
+        let restart_hint = format!(
+            "{}: {}",
+            key_name(ctx.settings.key_bindings.restart),
+            ctx.tr(Text::RestartHint)
+        );
+        draw_centered_text(
+            &restart_hint,
+            screen_height() / 2.0 + 100.0 * ui_scale(),
+            25.0,
+            palette.dim_text,
+        );
+        if ctx.run.score > ctx.previous_high_score {
+            draw_centered_text(
+                ctx.tr(Text::NewRecord),
+                screen_height() / 2.0 + 70.0 * ui_scale(),
+                30.0,
+                palette.reward,
+            );
+        }
+    }
+}
+
+struct EnterNameScreen {
+    /// Name being typed in for a leaderboard entry after a qualifying run
+    name: String,
+}
+
+impl Screen for EnterNameScreen {
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState> {
+        while let Some(character) = get_char_pressed() {
+            if !character.is_control() && self.name.chars().count() < MAX_NAME_LENGTH {
+                self.name.push(character);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.name.pop();
+        }
+        if is_key_pressed(KeyCode::Enter) && !self.name.trim().is_empty() {
+            ctx.leaderboards
+                .board_mut(ctx.run.mode, ctx.run.difficulty)
+                .insert(self.name.trim().to_string(), ctx.run.score);
+            ctx.leaderboards.save().ok();
+            return Some(GameState::Leaderboard);
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(GameState::MainMenu);
+        }
+        None
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        let palette = ctx.palette();
+        draw_centered_text(
+            ctx.tr(Text::EnterName),
+            screen_height() / 2.0 - 60.0 * ui_scale(),
+            50.0,
+            palette.text,
+        );
+        let text_dimensions = measure_ui_text(self.name.as_str(), 50);
+        let x = screen_width() / 2.0 - text_dimensions.width / 2.0;
+        draw_ui_text(
+            self.name.as_str(),
+            x,
+            screen_height() / 2.0,
+            50.0,
+            palette.reward,
+        );
+        if get_time().fract() < 0.5 {
+            draw_ui_text(
+                "_",
+                x + text_dimensions.width,
+                screen_height() / 2.0,
+                50.0,
+                palette.reward,
+            );
+        }
+    }
+}
+
+struct LeaderboardScreen {
+    /// Mode and difficulty of the leaderboard shown, or `None` for the ones
+    /// picked in the settings
+    tab: Option<(GameMode, Difficulty)>,
+}
+
+impl LeaderboardScreen {
+    fn tab(&self, ctx: &GameContext) -> (GameMode, Difficulty) {
+        // Practice has no leaderboard to start on
+        let mode = if ctx.settings.game_mode.is_ranked() {
+            ctx.settings.game_mode
+        } else {
+            GameMode::Endless
+        };
+        self.tab.unwrap_or((mode, ctx.settings.difficulty))
+    }
+}
+
+impl Screen for LeaderboardScreen {
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState> {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Space) {
+            return Some(GameState::MainMenu);
+        }
+        let (mut mode, mut difficulty) = self.tab(ctx);
+        if is_key_pressed(KeyCode::Left) {
+            mode = cycle(&GameMode::RANKED, mode, -1);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            mode = cycle(&GameMode::RANKED, mode, 1);
+        }
+        if is_key_pressed(KeyCode::Up) {
+            difficulty = cycle(&Difficulty::ALL, difficulty, -1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            difficulty = cycle(&Difficulty::ALL, difficulty, 1);
+        }
+        self.tab = Some((mode, difficulty));
+        None
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        let palette = ctx.palette();
+        let (mode, difficulty) = self.tab(ctx);
+        draw_centered_text(
+            ctx.tr(Text::Leaderboard),
+            80.0 * ui_scale(),
+            50.0,
+            palette.text,
+        );
+        let mode_tabs: Vec<(&str, bool)> = GameMode::RANKED
+            .iter()
+            .map(|&option| (ctx.tr(option.label()), option == mode))
+            .collect();
+        draw_tabs(&mode_tabs, 120.0 * ui_scale(), palette);
+        let difficulty_tabs: Vec<(&str, bool)> = Difficulty::ALL
+            .iter()
+            .map(|&option| (ctx.tr(option.label()), option == difficulty))
+            .collect();
+        draw_tabs(&difficulty_tabs, 150.0 * ui_scale(), palette);
+        draw_centered_text(
+            ctx.tr(Text::LeaderboardTabsHint),
+            screen_height() - 20.0 * ui_scale(),
+            20.0,
+            palette.faint_text,
+        );
+        let leaderboard = ctx.leaderboards.board(mode, difficulty);
+        if leaderboard.entries.is_empty() {
+            draw_centered_text(
+                ctx.tr(Text::NoResults),
+                200.0 * ui_scale(),
+                30.0,
+                palette.dim_text,
+            );
+        }
+        for (i, entry) in leaderboard.entries.iter().enumerate() {
+            let y = (200.0 + i as f32 * 35.0) * ui_scale();
+            draw_ui_text(
+                format!("{:>2}. {}", i + 1, entry.name).as_str(),
+                screen_width() / 2.0 - 200.0 * ui_scale(),
+                y,
+                30.0,
+                palette.text,
+            );
+            let score_text = entry.score.to_string();
+            let text_dimensions = measure_ui_text(score_text.as_str(), 30);
+            draw_ui_text(
+                score_text.as_str(),
+                screen_width() / 2.0 + 200.0 * ui_scale() - text_dimensions.width,
+                y,
+                30.0,
+                palette.text,
+            );
+        }
+    }
+}
+
+/// Totals over every run played, and which achievements have been unlocked.
+struct StatsScreen;
+
+impl Screen for StatsScreen {
+    fn update(&mut self, _ctx: &mut GameContext) -> Option<GameState> {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Space) {
+            return Some(GameState::MainMenu);
+        }
+        None
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        let palette = ctx.palette();
+        draw_centered_text(ctx.tr(Text::Stats), 100.0 * ui_scale(), 50.0, palette.text);
+        let stats = &ctx.lifetime_stats;
+        let seconds = stats.time_played as u64;
+        let rows = [
+            (Text::GamesPlayed, stats.games_played.to_string()),
+            (Text::EnemiesDestroyed, stats.enemies_destroyed.to_string()),
+            (Text::MaxCombo, format!("x{}", stats.best_combo)),
+            (
+                Text::TimePlayed,
+                format!(
+                    "{}:{:02}:{:02}",
+                    seconds / 3600,
+                    seconds / 60 % 60,
+                    seconds % 60
+                ),
+            ),
+        ];
+        for (i, (label, value)) in rows.iter().enumerate() {
+            let y = (160.0 + i as f32 * 35.0) * ui_scale();
+            draw_ui_text(
+                ctx.tr(*label),
+                screen_width() / 2.0 - 200.0 * ui_scale(),
+                y,
+                30.0,
+                palette.text,
+            );
+            let text_dimensions = measure_ui_text(value.as_str(), 30);
+            draw_ui_text(
+                value.as_str(),
+                screen_width() / 2.0 + 200.0 * ui_scale() - text_dimensions.width,
+                y,
+                30.0,
+                palette.text,
+            );
+        }
+        draw_centered_text(
+            ctx.tr(Text::Achievements),
+            340.0 * ui_scale(),
+            35.0,
+            palette.text,
+        );
+        for (i, achievement) in Achievement::ALL.into_iter().enumerate() {
+            let y = (380.0 + i as f32 * 30.0) * ui_scale();
+            let (mark, color) = if ctx.achievements.is_unlocked(achievement) {
+                ("+", palette.reward)
+            } else {
+                ("-", palette.faint_text)
+            };
+            draw_ui_text(
+                format!("{} {}", mark, ctx.tr(achievement.label())).as_str(),
+                screen_width() / 2.0 - 200.0 * ui_scale(),
+                y,
+                25.0,
+                color,
+            );
+        }
+    }
+}
+
+struct SettingsScreen {
+    /// Highlighted row
+    selection: usize,
+}
+
+impl SettingsScreen {
+    const ROW_HEIGHT: f32 = 25.0;
+
+    /// Returns to the main menu, in the top left corner.
+    fn back_button() -> Rect {
+        let scale = ui_scale();
+        Rect::new(20.0 * scale, 20.0 * scale, 160.0 * scale, 40.0 * scale)
+    }
+
+    /// Baseline of the text in row `index`.
+    fn row_y(index: usize) -> f32 {
+        (130.0 + index as f32 * Self::ROW_HEIGHT) * ui_scale()
+    }
+
+    /// Opens the key bindings, across from the back button.
+    fn keys_button() -> Rect {
+        let back = Self::back_button();
+        Rect::new(screen_width() - back.x - back.w, back.y, back.w, back.h)
+    }
+
+    /// The clickable area of row `index`.
+    fn row_rect(index: usize) -> Rect {
+        let scale = ui_scale();
+        Rect::new(
+            screen_width() / 2.0 - 200.0 * scale,
+            Self::row_y(index) - 20.0 * scale,
+            400.0 * scale,
+            Self::ROW_HEIGHT * scale,
+        )
+    }
+}
+
+impl Screen for SettingsScreen {
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState> {
+        let items = SettingsItem::ALL;
+        if is_key_pressed(KeyCode::Up) {
+            self.selection = (self.selection + items.len() - 1) % items.len();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selection = (self.selection + 1) % items.len();
+        }
+        let mut direction = 0;
+        if is_key_pressed(KeyCode::Left) {
+            direction = -1;
+        }
+        if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::Enter) {
+            direction = 1;
+        }
+        // Clicking a row picks it and steps it forwards
+        if let Some(index) = (0..items.len()).find(|&i| button_clicked(Self::row_rect(i))) {
+            self.selection = index;
+            direction = 1;
+        }
+        // A custom seed is typed in digit by digit
+        if items[self.selection] == SettingsItem::Seed && ctx.settings.seed_mode == SeedMode::Custom
+        {
+            let mut changed = false;
+            while let Some(character) = get_char_pressed() {
+                if let Some(digit) = character.to_digit(10) {
+                    ctx.settings.custom_seed = ctx
+                        .settings
+                        .custom_seed
+                        .saturating_mul(10)
+                        .saturating_add(digit as u64);
+                    changed = true;
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                ctx.settings.custom_seed /= 10;
+                changed = true;
+            }
+            if changed {
+                ctx.settings.save().ok();
+            }
+        }
+        if direction != 0 {
+            let item = items[self.selection];
+            ctx.settings.adjust(item, direction);
+            match item {
+                SettingsItem::Audio => ctx.audio.set_enabled(ctx.settings.audio_enabled),
+                SettingsItem::MusicVolume | SettingsItem::SfxVolume => {
+                    ctx.audio
+                        .set_volumes(ctx.settings.music_volume, ctx.settings.sfx_volume);
+                    ctx.audio.play_shoot();
+                }
+                _ => {}
+            }
+            ctx.settings.save().ok();
+        }
+        if is_key_pressed(KeyCode::K) || button_clicked(Self::keys_button()) {
+            return Some(GameState::KeyBindings);
+        }
+        if is_key_pressed(KeyCode::Escape) || button_clicked(Self::back_button()) {
+            return Some(GameState::MainMenu);
+        }
+        None
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        let palette = ctx.palette();
+        let items = SettingsItem::ALL;
+        draw_button(Self::back_button(), ctx.tr(Text::Back), false, palette);
+        draw_button(Self::keys_button(), ctx.tr(Text::Keys), false, palette);
+        draw_centered_text(
+            ctx.tr(Text::Settings),
+            100.0 * ui_scale(),
+            50.0,
+            palette.text,
+        );
+        let right = screen_width() / 2.0 + 200.0 * ui_scale();
+        for (i, item) in items.iter().enumerate() {
+            let y = Self::row_y(i);
+            let color = if i == self.selection {
+                palette.highlight
+            } else {
+                palette.text
+            };
+            draw_ui_text(
+                ctx.tr(item.label()),
+                screen_width() / 2.0 - 200.0 * ui_scale(),
+                y,
+                24.0,
+                color,
+            );
+            let draw_value = |value: &str| {
+                let text_dimensions = measure_ui_text(value, 24);
+                draw_ui_text(value, right - text_dimensions.width, y, 24.0, color);
+            };
+            match ctx.settings.value(*item) {
+                SettingValue::Text(value) => draw_value(value),
+                SettingValue::Number(value) => draw_value(&value.to_string()),
+                SettingValue::Percent(value) => {
+                    draw_value(&format!("{}%", (value * 100.0).round()))
+                }
+                SettingValue::Slider(fraction) => {
+                    let scale = ui_scale();
+                    let (width, height) = (150.0 * scale, 15.0 * scale);
+                    draw_rectangle(right - width, y - height, width * fraction, height, color);
+                    draw_rectangle_lines(right - width, y - height, width, height, 2.0, color);
+                }
+            }
+        }
+        draw_centered_text(
+            ctx.tr(Text::SettingsHelp),
+            Self::row_y(items.len()) + 20.0 * ui_scale(),
+            20.0,
+            palette.dim_text,
+        );
+    }
+}
+
+/// Lists the key bound to each action. Picking an action and pressing a key
+/// binds that key to it.
+struct KeyBindingsScreen {
+    /// Highlighted action
+    selection: usize,
+    /// Whether the next key pressed is bound to the highlighted action
+    capturing: bool,
+    /// Why the last key pressed couldn't be bound
+    notice: Option<String>,
+}
+
+impl KeyBindingsScreen {
+    const ROW_HEIGHT: f32 = 40.0;
+
+    /// Baseline of the text in row `index`.
+    fn row_y(index: usize) -> f32 {
+        (180.0 + index as f32 * Self::ROW_HEIGHT) * ui_scale()
+    }
+
+    /// The clickable area of row `index`.
+    fn row_rect(index: usize) -> Rect {
+        let scale = ui_scale();
+        Rect::new(
+            screen_width() / 2.0 - 200.0 * scale,
+            Self::row_y(index) - 30.0 * scale,
+            400.0 * scale,
+            Self::ROW_HEIGHT * scale,
+        )
+    }
+}
+
+impl Screen for KeyBindingsScreen {
+    fn update(&mut self, ctx: &mut GameContext) -> Option<GameState> {
+        let actions = Action::ALL;
+        if self.capturing {
+            let key = get_last_key_pressed()?;
+            if key == KeyCode::Backspace {
+                self.capturing = false;
+                return None;
+            }
+            if !actions[self.selection].can_bind(key) {
+                self.notice = Some(ctx.tr(Text::KeyNotAllowed).to_string());
+                return None;
+            }
+            match ctx.settings.key_bindings.bind(actions[self.selection], key) {
+                Ok(()) => {
+                    ctx.settings.save().ok();
+                    self.capturing = false;
+                    self.notice = None;
+                }
+                Err(other) => {
+                    self.notice = Some(format!(
+                        "{} {}",
+                        ctx.tr(Text::KeyInUse),
+                        ctx.tr(other.label())
+                    ));
+                }
+            }
+            return None;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.selection = (self.selection + actions.len() - 1) % actions.len();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selection = (self.selection + 1) % actions.len();
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            self.capturing = true;
+        }
+        if let Some(index) = (0..actions.len()).find(|&i| button_clicked(Self::row_rect(i))) {
+            self.selection = index;
+            self.capturing = true;
+        }
+        if is_key_pressed(KeyCode::Escape) || button_clicked(SettingsScreen::back_button()) {
+            return Some(GameState::Settings);
+        }
+        None
+    }
+
+    fn draw(&self, ctx: &mut GameContext) {
+        let palette = ctx.palette();
+        let actions = Action::ALL;
+        draw_button(
+            SettingsScreen::back_button(),
+            ctx.tr(Text::Back),
+            false,
+            palette,
+        );
+        draw_centered_text(ctx.tr(Text::Keys), 100.0 * ui_scale(), 50.0, palette.text);
+        let right = screen_width() / 2.0 + 200.0 * ui_scale();
+        for (i, action) in actions.iter().enumerate() {
+            let y = Self::row_y(i);
+            let color = if i == self.selection {
+                palette.highlight
+            } else {
+                palette.text
+            };
+            draw_ui_text(
+                ctx.tr(action.label()),
+                screen_width() / 2.0 - 200.0 * ui_scale(),
+                y,
+                30.0,
+                color,
+            );
+            // Blink the key being replaced while waiting for the new one
+            if self.capturing && i == self.selection && get_time().fract() < 0.5 {
+                continue;
+            }
+            let value = key_name(ctx.settings.key_bindings.key(*action));
+            let text_dimensions = measure_ui_text(value.as_str(), 30);
+            draw_ui_text(
+                value.as_str(),
+                right - text_dimensions.width,
+                y,
+                30.0,
+                color,
+            );
+        }
+        let help_y = Self::row_y(actions.len()) + 10.0 * ui_scale();
+        let help = if self.capturing {
+            Text::PressKey
+        } else {
+            Text::KeysHelp
+        };
+        draw_centered_text(ctx.tr(help), help_y, 20.0, palette.dim_text);
+        if let Some(notice) = &self.notice {
+            draw_centered_text(
+                notice.as_str(),
+                help_y + 30.0 * ui_scale(),
+                20.0,
+                palette.danger,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape() -> Shape {
+        Shape {
+            size: 8.0,
+            x: 100.0,
+            y: 100.0,
+            vx: 0.0,
+            vy: -300.0,
+            collided: false,
+            grazed: false,
+            damage: 1,
+            pierce_count: 0,
+            pierced: Vec::new(),
+        }
+    }
+
+    fn enemy() -> Enemy {
+        Enemy {
+            id: 7,
+            kind: EnemyKind::Grunt,
+            movement: Movement::Straight,
+            size: 32.0,
+            x: 200.0,
+            y: 50.0,
+            vx: 0.0,
+            vy: 100.0,
+            hp: 2,
+            collided: false,
+            grazed: false,
+            age: 3.0,
+            sway: 1.0,
+            shoot_timer: 1.0,
+            generation: 0,
+        }
+    }
+
+    /// A run with two enemies stacked on top of each other and a bullet
+    /// right on them.
+    fn stacked_enemies(pierce_count: u32) -> Run {
+        let mut run = Run::new(&Settings::default(), vec2(400.0, 500.0));
+        for id in 0..2 {
+            run.squares.push(Enemy {
+                id,
+                hp: 1,
+                ..enemy()
+            });
+        }
+        run.bullets.spawn(Shape {
+            x: 200.0,
+            y: 50.0,
+            pierce_count,
+            ..shape()
+        });
+        run
+    }
+
+    #[test]
+    fn a_bullet_destroys_only_one_of_two_stacked_enemies() {
+        let mut run = stacked_enemies(0);
+        let (destroyed, wounded) = run.hit_enemies();
+        assert_eq!(destroyed, vec![0]);
+        assert_eq!(wounded, 0);
+        assert!(run.squares[0].collided);
+        assert!(!run.squares[1].collided);
+        assert_eq!(run.squares[1].hp, 1);
+        assert!(run.bullets.iter().all(|bullet| bullet.collided));
+        assert_eq!(run.run_stats.shots_hit, 1);
+    }
+
+    #[test]
+    fn a_piercing_bullet_destroys_both_stacked_enemies() {
+        let mut run = stacked_enemies(1);
+        let (destroyed, _) = run.hit_enemies();
+        assert_eq!(destroyed, vec![0, 1]);
+        assert_eq!(run.run_stats.shots_hit, 1);
+    }
+
+    #[test]
+    fn reset_puts_a_played_run_back_to_the_start() {
+        let settings = Settings::default();
+        let spawn = vec2(400.0, 300.0);
+        let fresh = Run::new(&settings, spawn);
+        let mut run = Run::new(&settings, spawn);
+
+        run.squares.push(enemy());
+        run.bullets.spawn(shape());
+        run.enemy_bullets.push(shape());
+        run.power_ups
+            .push(PowerUp::new(PowerUpKind::Spread, 100.0, 100.0));
+        run.coins.push(shape());
+        run.floating_texts.push(FloatingText::new(100.0, 100.0, 50));
+        run.collision_grid.insert(0, enemy().rect());
+        run.circle.x = 10.0;
+        run.circle.y = 20.0;
+        run.circle.vx = 5.0;
+        run.circle.lives = 1;
+        run.circle.bombs = 0;
+        run.circle.clock = 60.0;
+        run.boss = Some(Boss {
+            size: 96.0,
+            x: 400.0,
+            y: 120.0,
+            hp: 10,
+            sweep: -1.0,
+        });
+        run.boss_warning = Some(1.0);
+        run.next_boss_score = 6000;
+        run.next_extra_life_score = 20000;
+        run.next_enemy_id = 30;
+        run.spawn_timer = 0.7;
+        run.spawn_columns.pick(&rand::RandGenerator::new());
+        run.elapsed_time = 90.0;
+        run.difficulty = Difficulty::Hard;
+        run.mode = GameMode::Waves;
+        run.inertia = !settings.inertia;
+        run.wave = 4;
+        run.wave_break = 0.0;
+        run.wave_enemies.push((EnemyKind::Fast, Movement::ZigZag));
+        run.time_left = 12.0;
+        run.score = 1234;
+        run.combo = 5;
+        run.combo_timer = 1.0;
+        run.run_stats = RunStats {
+            shots_fired: 40,
+            shots_hit: 20,
+            enemies_destroyed: 12,
+            time_survived: 90.0,
+            max_combo: 5,
+        };
+        run.direction_modifier = 0.3;
+        run.warp_intensity = 0.8;
+        run.time_scale = 0.5;
+        run.last_heartbeat = 9;
+        run.screen_shake = 6.0;
+        run.graze_pulse = 0.4;
+
+        run.reset(&settings, spawn);
+
+        assert!(run.squares.is_empty());
+        assert_eq!(run.bullets.iter().count(), 0);
+        assert!(run.enemy_bullets.is_empty());
+        assert!(run.power_ups.is_empty());
+        assert!(run.coins.is_empty());
+        assert!(run.floating_texts.is_empty());
+        assert!(run.collision_grid.query(enemy().rect()).is_empty());
+        assert_eq!(run.circle.x, fresh.circle.x);
+        assert_eq!(run.circle.y, fresh.circle.y);
+        assert_eq!(run.circle.vx, fresh.circle.vx);
+        assert_eq!(run.circle.lives, fresh.circle.lives);
+        assert_eq!(run.circle.bombs, fresh.circle.bombs);
+        assert_eq!(run.circle.clock, fresh.circle.clock);
+        assert!(run.boss.is_none());
+        assert_eq!(run.boss_warning, fresh.boss_warning);
+        assert_eq!(run.next_boss_score, fresh.next_boss_score);
+        assert_eq!(run.next_extra_life_score, fresh.next_extra_life_score);
+        assert_eq!(run.next_enemy_id, fresh.next_enemy_id);
+        assert_eq!(run.spawn_timer, fresh.spawn_timer);
+        assert!(run.spawn_columns == fresh.spawn_columns);
+        assert_eq!(run.elapsed_time, fresh.elapsed_time);
+        assert!(run.difficulty == fresh.difficulty);
+        assert!(run.mode == fresh.mode);
+        assert_eq!(run.inertia, fresh.inertia);
+        assert_eq!(run.wave, fresh.wave);
+        assert_eq!(run.wave_break, fresh.wave_break);
+        assert!(run.wave_enemies.is_empty());
+        assert_eq!(run.time_left, fresh.time_left);
+        assert_eq!(run.score, fresh.score);
+        assert_eq!(run.combo, fresh.combo);
+        assert_eq!(run.combo_timer, fresh.combo_timer);
+        assert_eq!(run.run_stats.shots_fired, fresh.run_stats.shots_fired);
+        assert_eq!(run.run_stats.shots_hit, fresh.run_stats.shots_hit);
+        assert_eq!(
+            run.run_stats.enemies_destroyed,
+            fresh.run_stats.enemies_destroyed
+        );
+        assert_eq!(run.run_stats.time_survived, fresh.run_stats.time_survived);
+        assert_eq!(run.run_stats.max_combo, fresh.run_stats.max_combo);
+        assert_eq!(run.direction_modifier, fresh.direction_modifier);
+        assert_eq!(run.warp_intensity, fresh.warp_intensity);
+        assert_eq!(run.time_scale, fresh.time_scale);
+        assert_eq!(run.last_heartbeat, fresh.last_heartbeat);
+        assert_eq!(run.screen_shake, fresh.screen_shake);
+        assert_eq!(run.graze_pulse, fresh.graze_pulse);
+    }
+}
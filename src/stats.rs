@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+
+use crate::storage::write_atomically;
+
+const STATS_FILE: &str = "stats.json";
+
+/// Counters for the run in progress, shown on the game over screen.
+#[derive(Default)]
+pub struct RunStats {
+    /// Bullets fired, counting each bullet of a spread shot
+    pub shots_fired: u32,
+    /// Bullets that hit at least one enemy or the boss
+    pub shots_hit: u32,
+    pub enemies_destroyed: u32,
+    /// Seconds of play, not counting pauses
+    pub time_survived: f32,
+    pub max_combo: u32,
+}
+
+impl RunStats {
+    /// Share of the bullets fired that hit something, in percent.
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            return 0.0;
+        }
+        self.shots_hit as f32 / self.shots_fired as f32 * 100.0
+    }
+}
+
+/// Totals over every run ever played, kept in `stats.json`.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub games_played: u32,
+    pub enemies_destroyed: u32,
+    pub best_combo: u32,
+    /// Seconds of play over all runs
+    pub time_played: f64,
+}
+
+impl LifetimeStats {
+    /// Reads the stats file, starting from zero if it is missing. A file that
+    /// can't be parsed is moved aside to a backup instead of being
+    /// overwritten.
+    pub fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(STATS_FILE) else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            let backup_file = format!("{}.bak", STATS_FILE);
+            eprintln!(
+                "Warning: couldn't read {} ({}), moving it to {}",
+                STATS_FILE, err, backup_file
+            );
+            fs::rename(STATS_FILE, backup_file).ok();
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        write_atomically(STATS_FILE, &contents)
+    }
+
+    /// Adds a finished run to the totals.
+    pub fn add_run(&mut self, run: &RunStats) {
+        self.games_played += 1;
+        self.enemies_destroyed += run.enemies_destroyed;
+        self.best_combo = self.best_combo.max(run.max_combo);
+        self.time_played += run.time_survived as f64;
+    }
+}
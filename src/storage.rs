@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+
+use crate::settings::{Difficulty, GameMode};
+
+const LEADERBOARDS_FILE: &str = "leaderboards.json";
+/// The single high score kept before each mode and difficulty had its own
+const LEGACY_HIGH_SCORE_FILE: &str = "highscore.dat";
+const LEADERBOARD_SIZE: usize = 10;
+
+/// Reads the high score kept before there were separate leaderboards. A
+/// file that can't be parsed is moved aside to a backup instead of being
+/// lost.
+fn load_legacy_high_score(path: &str) -> u32 {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return 0;
+    };
+    match contents.trim().parse::<u32>() {
+        Ok(score) => score,
+        Err(err) => {
+            let backup_file = format!("{}.bak", path);
+            eprintln!(
+                "Warning: couldn't read {} ({}), moving it to {}",
+                path, err, backup_file
+            );
+            fs::rename(path, backup_file).ok();
+            0
+        }
+    }
+}
+
+/// Writes to a temporary file and renames it into place, so a crash halfway
+/// through writing can't leave a corrupt file behind.
+pub fn write_atomically(path: &str, contents: &str) -> std::io::Result<()> {
+    let tmp_file = format!("{}.tmp", path);
+    fs::write(&tmp_file, contents)?;
+    fs::rename(&tmp_file, path)
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: u32,
+}
+
+/// The best scores of one mode and difficulty, highest first.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    /// Best score ever reached, whether or not a name was entered for it
+    #[serde(default)]
+    pub high_score: u32,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+/// Shown for a mode and difficulty that hasn't been played yet
+static EMPTY_LEADERBOARD: Leaderboard = Leaderboard {
+    high_score: 0,
+    entries: Vec::new(),
+};
+
+impl Leaderboard {
+    /// Whether a score is good enough to get a place on the leaderboard.
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < LEADERBOARD_SIZE
+                || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    pub fn insert(&mut self, name: String, score: u32) {
+        let position = self
+            .entries
+            .iter()
+            .position(|entry| score > entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries
+            .insert(position, LeaderboardEntry { name, score });
+        self.entries.truncate(LEADERBOARD_SIZE);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyedLeaderboard {
+    mode: GameMode,
+    difficulty: Difficulty,
+    leaderboard: Leaderboard,
+}
+
+/// A leaderboard for every mode and difficulty, since their scores can't be
+/// compared, kept together in `leaderboards.json`.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Leaderboards {
+    boards: Vec<KeyedLeaderboard>,
+}
+
+impl Leaderboards {
+    /// Reads the leaderboards file, carrying over the high score from older
+    /// versions of the game if there isn't one yet. A file that can't be
+    /// parsed is moved aside to a backup instead of being overwritten.
+    pub fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(LEADERBOARDS_FILE) else {
+            return Self::migrate();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            let backup_file = format!("{}.bak", LEADERBOARDS_FILE);
+            eprintln!(
+                "Warning: couldn't read {} ({}), moving it to {}",
+                LEADERBOARDS_FILE, err, backup_file
+            );
+            fs::rename(LEADERBOARDS_FILE, backup_file).ok();
+            Self::default()
+        })
+    }
+
+    /// Builds the leaderboards from the single high score that came before
+    /// them. It was set in endless mode, mostly on the default difficulty,
+    /// so that is where it goes. The old file is left alone unless it can't
+    /// be read.
+    fn migrate() -> Self {
+        let mut leaderboards = Self::default();
+        leaderboards
+            .board_mut(GameMode::Endless, Difficulty::Normal)
+            .high_score = load_legacy_high_score(LEGACY_HIGH_SCORE_FILE);
+        leaderboards.save().ok();
+        leaderboards
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        write_atomically(LEADERBOARDS_FILE, &contents)
+    }
+
+    pub fn board(&self, mode: GameMode, difficulty: Difficulty) -> &Leaderboard {
+        self.boards
+            .iter()
+            .find(|board| board.mode == mode && board.difficulty == difficulty)
+            .map_or(&EMPTY_LEADERBOARD, |board| &board.leaderboard)
+    }
+
+    /// The leaderboard of a mode and difficulty, added if it isn't there yet.
+    pub fn board_mut(&mut self, mode: GameMode, difficulty: Difficulty) -> &mut Leaderboard {
+        let index = match self
+            .boards
+            .iter()
+            .position(|board| board.mode == mode && board.difficulty == difficulty)
+        {
+            Some(index) => index,
+            None => {
+                self.boards.push(KeyedLeaderboard {
+                    mode,
+                    difficulty,
+                    leaderboard: Leaderboard::default(),
+                });
+                self.boards.len() - 1
+            }
+        };
+        &mut self.boards[index].leaderboard
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    #[test]
+    fn malformed_high_score_file_reads_as_zero_and_is_backed_up() {
+        let dir = std::env::temp_dir().join(format!("highscore-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("highscore.dat");
+        let path = path.to_str().unwrap();
+        let backup_file = format!("{}.bak", path);
+        fs::write(path, "not a number").unwrap();
+
+        assert_eq!(load_legacy_high_score(path), 0);
+        assert!(!Path::new(path).exists());
+        assert_eq!(fs::read_to_string(&backup_file).unwrap(), "not a number");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn readable_high_score_file_is_kept() {
+        let dir = std::env::temp_dir().join(format!("highscore-ok-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("highscore.dat");
+        let path = path.to_str().unwrap();
+        fs::write(path, "1234\n").unwrap();
+
+        assert_eq!(load_legacy_high_score(path), 1234);
+        assert!(Path::new(path).exists());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
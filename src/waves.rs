@@ -0,0 +1,109 @@
+use macroquad::rand::RandGenerator;
+
+use crate::entities::{EnemyKind, Movement};
+
+/// Seconds of calm before each wave, while its banner is shown
+pub const WAVE_BREAK_TIME: f32 = 3.0;
+/// Score for clearing a wave, multiplied by the wave number
+pub const WAVE_BONUS: u32 = 200;
+/// How much faster enemies get with each wave
+const WAVE_SPEED_INCREASE: f32 = 0.08;
+/// Most the wave speed increase can add up to
+const MAX_WAVE_SPEED_INCREASE: f32 = 0.8;
+
+/// How many of each kind of enemy make up a wave.
+struct SpawnTable {
+    grunts: u32,
+    fast: u32,
+    tanks: u32,
+    splitters: u32,
+    friendlies: u32,
+    /// Odds of each hostile enemy homing in on the player, one in this many,
+    /// or 0 for none
+    homing_odds: u32,
+}
+
+/// The first waves, one after the other. Waves after the last one keep
+/// adding enemies to it.
+const SPAWN_TABLES: [SpawnTable; 5] = [
+    SpawnTable {
+        grunts: 6,
+        fast: 0,
+        tanks: 0,
+        splitters: 0,
+        friendlies: 1,
+        homing_odds: 0,
+    },
+    SpawnTable {
+        grunts: 8,
+        fast: 3,
+        tanks: 0,
+        splitters: 0,
+        friendlies: 1,
+        homing_odds: 0,
+    },
+    SpawnTable {
+        grunts: 8,
+        fast: 4,
+        tanks: 2,
+        splitters: 1,
+        friendlies: 2,
+        homing_odds: 10,
+    },
+    SpawnTable {
+        grunts: 10,
+        fast: 6,
+        tanks: 3,
+        splitters: 2,
+        friendlies: 2,
+        homing_odds: 8,
+    },
+    SpawnTable {
+        grunts: 12,
+        fast: 8,
+        tanks: 5,
+        splitters: 3,
+        friendlies: 2,
+        homing_odds: 6,
+    },
+];
+
+/// The enemies of a wave, counting from 1, in the order they spawn.
+pub fn wave_enemies(wave: u32, rng: &RandGenerator) -> Vec<(EnemyKind, Movement)> {
+    let index = (wave.max(1) as usize - 1).min(SPAWN_TABLES.len() - 1);
+    let table = &SPAWN_TABLES[index];
+    // Waves past the tables grow by a few enemies each
+    let extra = wave.saturating_sub(SPAWN_TABLES.len() as u32);
+    let counts = [
+        (EnemyKind::Grunt, table.grunts + extra * 2),
+        (EnemyKind::Fast, table.fast + extra * 2),
+        (EnemyKind::Tank, table.tanks + extra),
+        (EnemyKind::Splitter, table.splitters + extra / 2),
+        (EnemyKind::Friendly, table.friendlies),
+    ];
+    let mut enemies = Vec::new();
+    for (kind, count) in counts {
+        for _ in 0..count {
+            let movement = if kind == EnemyKind::Friendly {
+                Movement::Straight
+            } else if table.homing_odds > 0 && rng.gen_range(0, table.homing_odds) == 0 {
+                Movement::Homing
+            } else if rng.gen_range(0, 4) == 0 {
+                Movement::ZigZag
+            } else {
+                Movement::Straight
+            };
+            enemies.push((kind, movement));
+        }
+    }
+    // Shuffle so the kinds arrive mixed together
+    for i in (1..enemies.len()).rev() {
+        enemies.swap(i, rng.gen_range(0, i + 1));
+    }
+    enemies
+}
+
+/// How much faster than normal the enemies of a wave move.
+pub fn wave_speed_scale(wave: u32) -> f32 {
+    1.0 + (WAVE_SPEED_INCREASE * wave.saturating_sub(1) as f32).min(MAX_WAVE_SPEED_INCREASE)
+}